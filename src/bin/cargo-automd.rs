@@ -0,0 +1,21 @@
+//! `cargo automd`: cargo runs `cargo-automd automd <args>`; the subcommand name is dropped
+//! and the rest is parsed like `automd-rs` (see `automd_rs::cli`).
+
+use automd_rs::cli::{self, Cli};
+use clap::{CommandFactory, FromArgMatches};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args: Vec<_> = std::env::args_os().collect();
+    if args.get(1).is_some_and(|arg| arg == "automd") {
+        args.remove(1);
+    }
+    let matches = Cli::command()
+        .name("cargo-automd")
+        .bin_name("cargo automd")
+        .get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(cli.log_filter()))
+        .init();
+    cli::run(&cli)
+}
@@ -0,0 +1,855 @@
+//! Command-line interface shared by the `automd-rs` binary: argument parsing and modes.
+
+use clap::{ArgAction, Parser, ValueEnum};
+use log::{info, trace, warn};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+/// How often `--watch` polls the watched files.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the watched files must stay unchanged before `--watch` runs again.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Update README blocks from Cargo.toml.
+#[derive(Debug, Parser)]
+#[command(name = "automd-rs", version, about)]
+pub struct Cli {
+    /// Path to Cargo.toml [default: nearest Cargo.toml at or above the current directory]
+    #[arg(long, value_name = "PATH")]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Markdown file or glob (e.g. 'docs/**/*.md') to update; repeatable [default: package.readme, or README.md next to the manifest]
+    #[arg(long = "readme", alias = "file", value_name = "PATH")]
+    pub readmes: Vec<PathBuf>,
+
+    /// Update the README of every member of the workspace containing the manifest
+    #[arg(long, conflicts_with = "readmes")]
+    pub workspace: bool,
+
+    /// Update the README of this workspace member only; repeatable
+    #[arg(short, long = "package", value_name = "SPEC", conflicts_with_all = ["readmes", "workspace"])]
+    pub packages: Vec<String>,
+
+    /// Change to DIR before doing anything
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Only log errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log more (-v debug, -vv trace)
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Report blocks that would change and exit non-zero instead of writing
+    #[arg(long, conflicts_with = "dry_run")]
+    pub check: bool,
+
+    /// Print a unified diff of the proposed changes instead of writing
+    #[arg(long, visible_alias = "diff")]
+    pub dry_run: bool,
+
+    /// Keep updating other blocks when one fails, then report every failure
+    #[arg(short, long)]
+    pub keep_going: bool,
+
+    /// Keep the previous content of each rewritten README in README.md.bak
+    #[arg(long)]
+    pub backup: bool,
+
+    /// Read a Markdown document from stdin and write the updated document to stdout
+    #[arg(
+        long,
+        conflicts_with_all = ["readmes", "workspace", "packages", "check", "dry_run", "keep_going", "backup", "format"]
+    )]
+    pub stdin: bool,
+
+    /// Keep running and update again whenever Cargo.toml, a target or a `file` block source changes (one package, text output only)
+    #[arg(long, conflicts_with_all = ["workspace", "packages", "check", "dry_run", "stdin", "format"])]
+    pub watch: bool,
+
+    /// Output format: log lines, or a JSON report of every file and block on stdout
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+}
+
+/// `--format`: how results are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Log lines on stderr (and diffs on stdout)
+    #[default]
+    Text,
+    /// A JSON report on stdout; logs still go to stderr
+    Json,
+}
+
+impl Cli {
+    /// Default env_logger filter for `--quiet` / `--verbose`; `RUST_LOG` still takes precedence.
+    pub fn log_filter(&self) -> &'static str {
+        if self.quiet {
+            return "error";
+        }
+        match self.verbose {
+            0 => "info",
+            1 => "debug",
+            _ => "trace",
+        }
+    }
+
+    /// Directory containing the manifest: `--manifest-path` may point at Cargo.toml or its
+    /// directory, and [`Error::CargoTomlNotFound`](crate::Error::CargoTomlNotFound) is
+    /// returned if that Cargo.toml does not exist; without it, the nearest Cargo.toml at or
+    /// above the current directory.
+    pub fn manifest_dir(&self) -> crate::Result<PathBuf> {
+        let Some(path) = &self.manifest_path else {
+            return Ok(locate_manifest_dir(Path::new(".")));
+        };
+        let (dir, manifest) = if path.is_dir() {
+            (path.clone(), path.join("Cargo.toml"))
+        } else {
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            (dir, path.clone())
+        };
+        if !manifest.is_file() {
+            return Err(crate::Error::CargoTomlNotFound);
+        }
+        Ok(dir)
+    }
+
+    /// Library options for the selected flags.
+    pub fn run_options(&self) -> crate::RunOptions {
+        crate::RunOptions {
+            keep_going: self.keep_going,
+            backup: self.backup,
+        }
+    }
+}
+
+/// Outcome for one README, used for exit status and the workspace summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Written,
+    UpToDate,
+    WouldChange,
+    Stale,
+    Failed,
+}
+
+impl Status {
+    fn is_ok(self) -> bool {
+        !matches!(self, Status::Stale | Status::Failed)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Status::Written => "updated",
+            Status::UpToDate => "up to date",
+            Status::WouldChange => "would change",
+            Status::Stale => "out of date",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+/// An error for one README, located at a line when it comes from a block or tag.
+#[derive(Debug, Serialize)]
+struct FileError {
+    line: Option<usize>,
+    column: Option<usize>,
+    block: Option<String>,
+    message: String,
+}
+
+impl FileError {
+    /// One entry per failed block or malformed tag, else a single file-level entry.
+    fn from_error(error: &crate::Error) -> Vec<Self> {
+        match error {
+            crate::Error::Blocks(failures) => failures
+                .iter()
+                .map(|f| FileError {
+                    line: Some(f.line),
+                    column: None,
+                    block: Some(f.name.clone()),
+                    message: f.source.to_string(),
+                })
+                .collect(),
+            crate::Error::MalformedBlocks(diagnostics) => diagnostics
+                .iter()
+                .map(|d| FileError {
+                    line: Some(d.line),
+                    column: Some(d.column),
+                    block: None,
+                    message: d.message.clone(),
+                })
+                .collect(),
+            e => vec![FileError {
+                line: None,
+                column: None,
+                block: None,
+                message: e.to_string(),
+            }],
+        }
+    }
+}
+
+/// Everything that happened to one README; logged as it happens and printed by `--format json`.
+#[derive(Debug, Serialize)]
+struct FileReport {
+    path: PathBuf,
+    /// Workspace member the file belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    status: Status,
+    modified: bool,
+    blocks: Vec<crate::BlockReport>,
+    errors: Vec<FileError>,
+    /// Unified diff in `--dry-run` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+impl FileReport {
+    fn new(path: &Path, status: Status) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            package: None,
+            status,
+            modified: false,
+            blocks: Vec::new(),
+            errors: Vec::new(),
+            diff: None,
+        }
+    }
+
+    /// Logs `error` (each failed block or malformed tag as its own `path:line:` entry) and records it.
+    fn failed(path: &Path, error: &crate::Error) -> Self {
+        match error {
+            crate::Error::Blocks(failures) => {
+                for failure in failures {
+                    warn!(
+                        "error: {}:{}: block '{}': {}",
+                        path.display(),
+                        failure.line,
+                        failure.name,
+                        failure.source
+                    );
+                }
+            }
+            crate::Error::MalformedBlocks(diagnostics) => {
+                for diagnostic in diagnostics {
+                    warn!("error: {}:{}", path.display(), diagnostic);
+                }
+            }
+            e => warn!("error: {}: {}", path.display(), e),
+        }
+        let mut report = Self::new(path, Status::Failed);
+        report.errors = FileError::from_error(error);
+        report
+    }
+
+    /// Marks the file failed if blocks failed with `--keep-going`, logging each one; the
+    /// blocks and whether the file was written are kept.
+    fn record_block_errors(&mut self) {
+        let errors: Vec<FileError> = self
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                let message = block.error.clone()?;
+                warn!(
+                    "error: {}:{}: block '{}': {}",
+                    self.path.display(),
+                    block.start_line,
+                    block.name,
+                    message
+                );
+                Some(FileError {
+                    line: Some(block.start_line),
+                    column: None,
+                    block: Some(block.name.clone()),
+                    message,
+                })
+            })
+            .collect();
+        if !errors.is_empty() {
+            self.status = Status::Failed;
+            self.errors = errors;
+        }
+    }
+}
+
+/// `--format json` output.
+#[derive(Debug, Serialize)]
+struct JsonOutput<'a> {
+    ok: bool,
+    /// Error that stopped the run before any file was processed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    files: &'a [FileReport],
+}
+
+/// Runs the CLI after logging is initialised. Returns failure if any README errored
+/// (or, with `--check`, is stale).
+pub fn run(cli: &Cli) -> ExitCode {
+    trace!("cli: {:?}", cli);
+    if cli.stdin {
+        return match run_stdin(cli) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                warn!("error: <stdin>: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if cli.watch {
+        return run_watch(cli);
+    }
+    let result = run_files(cli);
+    let (files, error) = match &result {
+        Ok(files) => (files.as_slice(), None),
+        Err(e) => {
+            warn!("error: {}", e);
+            (&[][..], Some(e.to_string()))
+        }
+    };
+    let ok = error.is_none() && files.iter().all(|f| f.status.is_ok());
+    if cli.format == Format::Json {
+        let output = JsonOutput { ok, error, files };
+        let written = serde_json::to_string_pretty(&output)
+            .map_err(|e| e.to_string())
+            .and_then(|json| writeln!(std::io::stdout(), "{}", json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("error: {}", e);
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn change_dir(cli: &Cli) -> std::result::Result<(), String> {
+    if let Some(cwd) = &cli.cwd {
+        std::env::set_current_dir(cwd).map_err(|e| format!("{}: {}", cwd.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Filter mode: stdin → [`crate::update_readme`] → stdout. Nothing is printed on error,
+/// so editors keep the original buffer.
+fn run_stdin(cli: &Cli) -> std::result::Result<(), String> {
+    change_dir(cli)?;
+    let manifest_dir = cli.manifest_dir().map_err(|e| e.to_string())?;
+    filter(&manifest_dir, std::io::stdin(), std::io::stdout())
+}
+
+/// Reads a document from `input` and writes it updated with the manifest in `manifest_dir`
+/// to `output`; `output` is only written once the whole document was updated.
+fn filter(
+    manifest_dir: &Path,
+    mut input: impl Read,
+    mut output: impl Write,
+) -> std::result::Result<(), String> {
+    let mut content = String::new();
+    input
+        .read_to_string(&mut content)
+        .map_err(|e| e.to_string())?;
+    let updated = (|| {
+        let manifest = crate::parse_manifest(manifest_dir)?;
+        let automd = crate::Config::load(manifest_dir, &manifest)?;
+        let context =
+            crate::UpdateContext::new(manifest, manifest_dir.to_path_buf()).with_automd(automd);
+        crate::update_readme(&content, &crate::DefaultHandler, &context)
+    })()
+    .map_err(|e| match e {
+        crate::Error::MalformedBlocks(diagnostics) => diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        e => e.to_string(),
+    })?;
+    output
+        .write_all(updated.as_bytes())
+        .and_then(|()| output.flush())
+        .map_err(|e| e.to_string())
+}
+
+/// Directory cargo would take the manifest from when run in `dir`: `dir` itself if it has a
+/// Cargo.toml, else the nearest ancestor with one (e.g. `crates/b` from `crates/b/src`).
+fn locate_manifest_dir(dir: &Path) -> PathBuf {
+    if dir.join("Cargo.toml").is_file() {
+        return dir.to_path_buf();
+    }
+    crate::parser::workspace::find_manifest_dir(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Processes every target README; errors here (bad `--cwd`, workspace or config) stop the run.
+fn run_files(cli: &Cli) -> std::result::Result<Vec<FileReport>, String> {
+    change_dir(cli)?;
+    let manifest_dir = cli.manifest_dir().map_err(|e| e.to_string())?;
+    update_files(cli, &manifest_dir)
+}
+
+/// [`run_files`] once the manifest directory is known.
+fn update_files(cli: &Cli, manifest_dir: &Path) -> std::result::Result<Vec<FileReport>, String> {
+    trace!("manifest_dir: {:?}", manifest_dir);
+
+    let virtual_manifest =
+        cli.readmes.is_empty() && crate::parser::workspace::is_virtual(manifest_dir);
+    if cli.workspace || !cli.packages.is_empty() || virtual_manifest {
+        return run_packages(cli, manifest_dir).map_err(|e| e.to_string());
+    }
+    Ok(targets(cli, manifest_dir)?
+        .iter()
+        .map(|readme_path| {
+            trace!("readme_path: {:?}", readme_path);
+            run_one(cli, manifest_dir, readme_path)
+        })
+        .collect())
+}
+
+/// Files to update outside workspace mode: `--readme` paths and globs, or the defaults.
+fn targets(cli: &Cli, manifest_dir: &Path) -> std::result::Result<Vec<PathBuf>, String> {
+    let readme_paths = if cli.readmes.is_empty() {
+        default_targets(manifest_dir).map_err(|e| e.to_string())?
+    } else {
+        crate::targets::expand(&cli.readmes).map_err(|e| e.to_string())?
+    };
+    if readme_paths.is_empty() {
+        info!("no README to update");
+    }
+    Ok(readme_paths)
+}
+
+/// Updates the targets, then polls the files they depend on and updates again on every
+/// change. The snapshot is taken after writing, so the tool's own writes are ignored.
+/// Only returns if `--cwd` fails or the manifest is missing.
+fn run_watch(cli: &Cli) -> ExitCode {
+    if let Err(e) = change_dir(cli) {
+        warn!("error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    let manifest_dir = match cli.manifest_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    loop {
+        let targets = targets(cli, &manifest_dir).unwrap_or_else(|e| {
+            warn!("error: {}", e);
+            Vec::new()
+        });
+        for readme_path in &targets {
+            run_one(cli, &manifest_dir, readme_path);
+        }
+        // Re-expanded on every poll, so a file newly matching a glob triggers a run.
+        let patterns = if cli.readmes.is_empty() {
+            crate::Config::files(&manifest_dir).unwrap_or_default()
+        } else {
+            cli.readmes.clone()
+        };
+        let snapshot =
+            crate::watch::Snapshot::take(crate::watch::watched_files(&manifest_dir, &targets))
+                .with_patterns(&patterns);
+        info!(
+            "watching {} file(s) for changes, press Ctrl-C to stop",
+            snapshot.paths().len()
+        );
+        for path in crate::watch::wait_for_change(&snapshot, WATCH_INTERVAL, WATCH_DEBOUNCE) {
+            info!("{} changed", path.display());
+        }
+    }
+}
+
+/// Files to update when no `--readme` is given: `files` from automd.toml, else the
+/// manifest's `package.readme` (none when it is `false`).
+fn default_targets(manifest_dir: &Path) -> crate::Result<Vec<PathBuf>> {
+    if let Some(files) = config_files(manifest_dir)? {
+        return Ok(files);
+    }
+    let manifest = crate::parse_manifest(manifest_dir)?;
+    Ok(manifest.readme_path(manifest_dir).into_iter().collect())
+}
+
+/// `files` from the automd.toml next to the manifest, with globs expanded, if it lists any.
+fn config_files(manifest_dir: &Path) -> crate::Result<Option<Vec<PathBuf>>> {
+    let files = crate::targets::expand(&crate::Config::files(manifest_dir)?)?;
+    trace!("config files: {:?}", files);
+    Ok((!files.is_empty()).then_some(files))
+}
+
+/// Runs the README of each package selected by `--workspace` / `-p` (or the default members
+/// of a virtual manifest) with its own manifest, then logs a per-crate summary.
+fn run_packages(cli: &Cli, manifest_dir: &Path) -> crate::Result<Vec<FileReport>> {
+    let members = crate::select_packages(manifest_dir, &cli.packages, cli.workspace)?;
+    trace!("members: {:?}", members);
+
+    let mut summary = Vec::with_capacity(members.len());
+    for member in &members {
+        let readme_paths = match config_files(&member.manifest_dir) {
+            Ok(Some(files)) => files,
+            Ok(None) => member.readme_path.iter().cloned().collect(),
+            Err(e) => {
+                let mut report = FileReport::failed(&member.manifest_dir, &e);
+                report.package = Some(member.name.clone());
+                summary.push(report);
+                continue;
+            }
+        };
+        if readme_paths.is_empty() {
+            info!("{}: no README, skipped", member.name);
+            continue;
+        }
+        for readme_path in readme_paths {
+            let mut report = run_one(cli, &member.manifest_dir, &readme_path);
+            report.package = Some(member.name.clone());
+            summary.push(report);
+        }
+    }
+
+    let failed = summary.iter().filter(|r| !r.status.is_ok()).count();
+    for report in &summary {
+        info!(
+            "{} ({}): {}",
+            report.package.as_deref().unwrap_or_default(),
+            report.path.display(),
+            report.status.label()
+        );
+    }
+    info!("workspace: {} files, {} failed", summary.len(), failed);
+    Ok(summary)
+}
+
+fn run_one(cli: &Cli, manifest_dir: &Path, readme_path: &Path) -> FileReport {
+    let handler = &crate::DefaultHandler;
+    let options = cli.run_options();
+    if cli.check || cli.dry_run {
+        let out = match crate::dry_run_with_options(manifest_dir, readme_path, handler, &options) {
+            Ok(out) => out,
+            Err(e) => return FileReport::failed(readme_path, &e),
+        };
+        let status = if cli.check {
+            let mut stale = false;
+            for block in out.blocks.iter().filter(|b| b.changed) {
+                warn!(
+                    "{}:{}: block '{}' is out of date",
+                    readme_path.display(),
+                    block.start_line,
+                    block.name
+                );
+                stale = true;
+            }
+            if !stale {
+                info!("{} is up to date", readme_path.display());
+            }
+            if stale {
+                Status::Stale
+            } else {
+                Status::UpToDate
+            }
+        } else if out.diff.is_empty() {
+            info!("{}: no changes", readme_path.display());
+            Status::UpToDate
+        } else {
+            if cli.format == Format::Text {
+                print!("{}", out.diff);
+            }
+            Status::WouldChange
+        };
+        let mut report = FileReport::new(readme_path, status);
+        report.diff = (cli.dry_run && !out.diff.is_empty()).then_some(out.diff);
+        report.blocks = out.blocks;
+        report.record_block_errors();
+        return report;
+    }
+
+    match crate::run_with_options(manifest_dir, readme_path, handler, &options) {
+        Ok(run) => {
+            for block in run.changed_blocks() {
+                info!(
+                    "{}:{}: block '{}' updated ({:.1?})",
+                    readme_path.display(),
+                    block.start_line,
+                    block.name,
+                    block.duration
+                );
+            }
+            let status = if run.modified {
+                info!("{} updated", readme_path.display());
+                Status::Written
+            } else {
+                info!("{} is up to date", readme_path.display());
+                Status::UpToDate
+            };
+            let mut report = FileReport::new(readme_path, status);
+            report.modified = run.modified;
+            report.blocks = run.blocks;
+            report.record_block_errors();
+            report
+        }
+        Err(e) => FileReport::failed(readme_path, &e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("automd-rs").chain(args.iter().copied())).unwrap()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let cli = parse(&[]);
+        assert_eq!(cli.log_filter(), "info");
+        assert_eq!(cli.manifest_dir().unwrap(), PathBuf::from("."));
+        assert!(cli.readmes.is_empty());
+        assert!(!cli.check);
+        assert!(!cli.dry_run);
+    }
+
+    #[test]
+    fn test_manifest_path_and_readmes() {
+        let dir = std::env::temp_dir().join("automd_rs_test_cli_manifest_path");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("crates/foo/src")).unwrap();
+        std::fs::write(
+            dir.join("crates/foo/Cargo.toml"),
+            "[package]\nname = \"foo\"\n",
+        )
+        .unwrap();
+        let manifest = dir.join("crates/foo/Cargo.toml");
+        let cli = parse(&["--manifest-path", manifest.to_str().unwrap()]);
+        assert_eq!(cli.manifest_dir().unwrap(), dir.join("crates/foo"));
+        let cli = parse(&["--manifest-path", dir.join("crates/foo").to_str().unwrap()]);
+        assert_eq!(cli.manifest_dir().unwrap(), dir.join("crates/foo"));
+        for missing in [dir.join("typo/Cargo.toml"), dir.join("crates/foo/src")] {
+            let cli = parse(&["--manifest-path", missing.to_str().unwrap()]);
+            assert!(matches!(
+                cli.manifest_dir(),
+                Err(crate::Error::CargoTomlNotFound)
+            ));
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let cli = parse(&["--readme", "README.md", "--readme", "docs/index.md"]);
+        assert_eq!(
+            cli.readmes,
+            [PathBuf::from("README.md"), PathBuf::from("docs/index.md")]
+        );
+        let cli = parse(&["--file", "docs/**/*.md"]);
+        assert_eq!(cli.readmes, [PathBuf::from("docs/**/*.md")]);
+    }
+
+    #[test]
+    fn test_log_filter() {
+        assert_eq!(parse(&["-q"]).log_filter(), "error");
+        assert_eq!(parse(&["-v"]).log_filter(), "debug");
+        assert_eq!(parse(&["-vv"]).log_filter(), "trace");
+    }
+
+    #[test]
+    fn test_modes() {
+        assert!(parse(&["--check"]).check);
+        assert!(parse(&["--diff"]).dry_run);
+        assert!(Cli::try_parse_from(["automd-rs", "--check", "--dry-run"]).is_err());
+        assert!(Cli::try_parse_from(["automd-rs", "-q", "-v"]).is_err());
+        assert!(parse(&["--workspace"]).workspace);
+        assert!(parse(&["-k"]).run_options().keep_going);
+        assert!(!parse(&[]).run_options().keep_going);
+        assert!(parse(&["--backup"]).run_options().backup);
+        assert_eq!(parse(&[]).format, Format::Text);
+        assert!(parse(&["--stdin", "--manifest-path", "x/Cargo.toml"]).stdin);
+        assert!(parse(&["--watch", "--readme", "a.md"]).watch);
+        assert!(Cli::try_parse_from(["automd-rs", "--watch", "--check"]).is_err());
+        for flag in [&["--workspace"][..], &["-p", "a"], &["--format", "text"]] {
+            let args = ["automd-rs", "--watch"].iter().chain(flag);
+            assert!(Cli::try_parse_from(args).is_err(), "{flag:?}");
+        }
+        assert_eq!(parse(&["-p", "a", "--package", "b"]).packages, ["a", "b"]);
+        assert!(Cli::try_parse_from(["automd-rs", "-p", "a", "--workspace"]).is_err());
+        assert!(Cli::try_parse_from(["automd-rs", "--stdin", "--check"]).is_err());
+        assert!(Cli::try_parse_from(["automd-rs", "--stdin", "--readme", "a.md"]).is_err());
+        assert_eq!(parse(&["--format", "json"]).format, Format::Json);
+        assert!(Cli::try_parse_from(["automd-rs", "--format", "yaml"]).is_err());
+        assert!(Cli::try_parse_from(["automd-rs", "--workspace", "--readme", "a.md"]).is_err());
+    }
+
+    #[test]
+    fn test_file_error_from_error() {
+        let error = crate::Error::Blocks(vec![crate::BlockFailure {
+            name: "file".to_string(),
+            line: 12,
+            source: crate::Error::BlockHandler("file".to_string(), "missing".to_string()),
+        }]);
+        let errors = FileError::from_error(&error);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(12));
+        assert_eq!(errors[0].block.as_deref(), Some("file"));
+
+        let report = FileReport::failed(Path::new("README.md"), &error);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["errors"][0]["line"], 12);
+        assert_eq!(
+            json["errors"][0]["message"],
+            "Block handler 'file': missing"
+        );
+        assert!(json.get("diff").is_none());
+    }
+
+    #[test]
+    fn test_filter() {
+        let dir = std::env::temp_dir().join("automd_rs_test_cli_filter");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"filtered\"\n").unwrap();
+        let input = "# Title\r\n<!-- automdrs:cargo-add -->\r\nold\r\n<!-- /automdrs -->\r\n";
+        let mut output = Vec::new();
+        filter(&dir, input.as_bytes(), &mut output).unwrap();
+        let context = crate::UpdateContext::new(crate::parse_manifest(&dir).unwrap(), dir.clone());
+        let expected = crate::update_readme(input, &crate::DefaultHandler, &context).unwrap();
+        assert_eq!(output, expected.as_bytes());
+        assert!(expected.contains("cargo add filtered\r\n"));
+
+        let mut output = Vec::new();
+        let err = filter(
+            &dir,
+            "<!-- automdrs:cargo-add -->\n".as_bytes(),
+            &mut output,
+        );
+        assert!(err.unwrap_err().contains("never closed"));
+        assert!(output.is_empty());
+
+        let mut output = Vec::new();
+        let err = filter(
+            &dir,
+            "<!-- automdrs:file src=\"missing.md\" -->\n<!-- /automdrs -->\n".as_bytes(),
+            &mut output,
+        );
+        assert!(err.is_err());
+        assert!(output.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_run_from_member_subdir() {
+        let dir = std::env::temp_dir().join("automd_rs_test_cli_subdir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        let readme = "<!-- automdrs:cargo-add -->\n<!-- /automdrs -->\n<!-- automdrs:file src=\"snippet.txt\" -->\n<!-- /automdrs -->\n";
+        for name in ["a", "b"] {
+            let crate_dir = dir.join("crates").join(name);
+            std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+            std::fs::write(
+                crate_dir.join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+            std::fs::write(crate_dir.join("snippet.txt"), format!("from {name}\n")).unwrap();
+            std::fs::write(crate_dir.join("README.md"), readme).unwrap();
+        }
+        let cli = parse(&[]);
+
+        let manifest_dir = locate_manifest_dir(&dir.join("crates/b/src"));
+        assert_eq!(
+            manifest_dir.canonicalize().unwrap(),
+            dir.join("crates/b").canonicalize().unwrap()
+        );
+        let reports = update_files(&cli, &manifest_dir).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, Status::Written);
+        assert!(reports[0].path.ends_with("crates/b/README.md"));
+        let updated = std::fs::read_to_string(dir.join("crates/b/README.md")).unwrap();
+        assert!(updated.contains("cargo add b"));
+        assert!(updated.contains("from b"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("crates/a/README.md")).unwrap(),
+            readme
+        );
+
+        let manifest_dir = locate_manifest_dir(&dir.join("docs"));
+        let reports = update_files(&cli, &manifest_dir).unwrap();
+        let packages: Vec<_> = reports.iter().map(|r| r.package.as_deref()).collect();
+        assert_eq!(packages, [Some("a"), Some("b")]);
+        assert!(
+            std::fs::read_to_string(dir.join("crates/a/README.md"))
+                .unwrap()
+                .contains("from a")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_keep_going_json() {
+        let dir = std::env::temp_dir().join("automd_rs_test_cli_keep_going");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"kg\"\n").unwrap();
+        let readme = dir.join("README.md");
+        std::fs::write(
+            &readme,
+            "<!-- automdrs:cargo-add -->\n<!-- /automdrs -->\n<!-- automdrs:file src=\"missing.md\" -->\nkept\n<!-- /automdrs -->\n",
+        )
+        .unwrap();
+        let cli = parse(&["--keep-going", "--format", "json"]);
+        let report = run_one(&cli, &dir, &readme);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["modified"], true);
+        assert_eq!(json["blocks"].as_array().unwrap().len(), 2);
+        assert_eq!(json["blocks"][0]["changed"], true);
+        assert_eq!(json["blocks"][1]["new_body"][0], "kept");
+        assert!(json["blocks"][1]["error"].is_string());
+        assert_eq!(json["errors"][0]["line"], 3);
+        assert_eq!(json["errors"][0]["block"], "file");
+        assert!(
+            std::fs::read_to_string(&readme)
+                .unwrap()
+                .contains("cargo add kg")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_default_targets() {
+        let dir = std::env::temp_dir().join("automd_rs_test_default_targets");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = |readme: &str| {
+            std::fs::write(
+                dir.join("Cargo.toml"),
+                format!("[package]\nname = \"t\"\n{readme}"),
+            )
+            .unwrap();
+        };
+        manifest("");
+        assert!(default_targets(&dir).unwrap().is_empty());
+        std::fs::write(dir.join("README.md"), "").unwrap();
+        assert_eq!(default_targets(&dir).unwrap(), [dir.join("README.md")]);
+        manifest("readme = \"docs/README.md\"\n");
+        assert_eq!(default_targets(&dir).unwrap(), [dir.join("docs/README.md")]);
+        manifest("readme = false\n");
+        assert!(default_targets(&dir).unwrap().is_empty());
+        std::fs::write(dir.join("automd.toml"), "files = [\"GUIDE.md\"]\n").unwrap();
+        assert_eq!(default_targets(&dir).unwrap(), [dir.join("GUIDE.md")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,278 @@
+//! Project configuration: block option defaults from `[workspace.metadata.automd]`,
+//! `[package.metadata.automd]` and a standalone `automd.toml`.
+
+use crate::error::{Error, Result};
+use crate::parser::cargo::ParsedManifest;
+use crate::parser::tag_options::parse_tag_options;
+use crate::parser::workspace;
+use log::{trace, warn};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Config file names looked up next to the manifest, in order.
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["automd.toml", ".automdrs.toml"];
+
+/// Option defaults for blocks; options written in an open tag override them.
+///
+/// ```toml
+/// [package.metadata.automd]
+/// author = "YONGQI"            # shared by every block
+/// badges = ["version", "docs"] # flags for one block
+///
+/// [package.metadata.automd.contributors]
+/// license = "MIT"              # key/values for one block
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// Top-level scalars, passed to every block.
+    pub options: BTreeMap<String, String>,
+    /// Per-block options by block name; list items become `item = "true"`.
+    pub blocks: BTreeMap<String, BTreeMap<String, String>>,
+    /// Blocks whose current content is kept as is.
+    pub disabled: BTreeSet<String>,
+    /// Block name → template rendered instead of the handler's output.
+    pub templates: BTreeMap<String, String>,
+    /// Files to update, resolved against the config file's directory; empty means the default README.
+    pub files: Vec<PathBuf>,
+}
+
+/// `automd.toml` / `.automdrs.toml`:
+///
+/// ```toml
+/// files = ["README.md", "docs/index.md"]
+/// disabled = ["contributors"]
+///
+/// [defaults]      # same shape as [package.metadata.automd]
+/// badges = ["version", "docs"]
+///
+/// [templates]
+/// cargo-install = "```sh\ncargo binstall {{name}}\n```"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    files: Vec<PathBuf>,
+    disabled: Vec<String>,
+    defaults: toml::Table,
+    templates: BTreeMap<String, String>,
+}
+
+/// Strings, booleans and numbers as tag option values; other TOML values are unsupported.
+fn scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Reads the `automd` table of a `[package.metadata]` or `[workspace.metadata]` table.
+    pub fn from_metadata(metadata: &toml::Table) -> Self {
+        match metadata.get("automd") {
+            Some(toml::Value::Table(table)) => Self::from_table(table),
+            Some(_) => {
+                warn!("metadata.automd must be a table, ignored");
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    /// Builds a config from the contents of an `automd` table.
+    pub fn from_table(table: &toml::Table) -> Self {
+        let mut config = Self::default();
+        for (key, value) in table {
+            match value {
+                toml::Value::Table(options) => {
+                    let block = config.blocks.entry(key.clone()).or_default();
+                    for (option, value) in options {
+                        match scalar(value) {
+                            Some(value) => {
+                                block.insert(option.clone(), value);
+                            }
+                            None => warn!("automd.{key}.{option}: unsupported value, ignored"),
+                        }
+                    }
+                }
+                toml::Value::Array(flags) => {
+                    let block = config.blocks.entry(key.clone()).or_default();
+                    for flag in flags {
+                        match flag.as_str() {
+                            Some(flag) => {
+                                block.insert(flag.to_string(), "true".to_string());
+                            }
+                            None => warn!("automd.{key}: list items must be strings, ignored"),
+                        }
+                    }
+                }
+                value => match scalar(value) {
+                    Some(value) => {
+                        config.options.insert(key.clone(), value);
+                    }
+                    None => warn!("automd.{key}: unsupported value, ignored"),
+                },
+            }
+        }
+        config
+    }
+
+    /// Path of the first config file from [`CONFIG_FILE_NAMES`] in `manifest_dir`.
+    pub fn find_file(manifest_dir: &Path) -> Option<PathBuf> {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| manifest_dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads a config file; `files` are resolved against the file's directory.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        trace!("reading config file: {:?}", path);
+        let content = std::fs::read_to_string(path)?;
+        let file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let mut config = Self::from_table(&file.defaults);
+        config.disabled = file.disabled.into_iter().collect();
+        config.templates = file.templates;
+        config.files = file.files.iter().map(|f| base_dir.join(f)).collect();
+        Ok(config)
+    }
+
+    /// Overlays `other` on top of `self`: its values win, option by option.
+    pub fn merge(&mut self, other: Config) {
+        self.options.extend(other.options);
+        for (name, options) in other.blocks {
+            self.blocks.entry(name).or_default().extend(options);
+        }
+        self.disabled.extend(other.disabled);
+        self.templates.extend(other.templates);
+        if !other.files.is_empty() {
+            self.files = other.files;
+        }
+    }
+
+    /// `[workspace.metadata.automd]` (if the crate is in a workspace), overlaid with
+    /// the crate's own `[package.metadata.automd]`, then with `automd.toml` if present.
+    pub fn load(manifest_dir: &Path, manifest: &ParsedManifest) -> Result<Self> {
+        let mut config = Self::from_metadata(&workspace::metadata(manifest_dir)?);
+        config.merge(Self::from_metadata(&manifest.metadata));
+        if let Some(path) = Self::find_file(manifest_dir) {
+            config.merge(Self::from_file(&path)?);
+        }
+        trace!("automd config: {:?}", config);
+        Ok(config)
+    }
+
+    /// Files listed in the config file next to the manifest, if any.
+    pub fn files(manifest_dir: &Path) -> Result<Vec<PathBuf>> {
+        match Self::find_file(manifest_dir) {
+            Some(path) => Ok(Self::from_file(&path)?.files),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn is_disabled(&self, block_name: &str) -> bool {
+        self.disabled.contains(block_name)
+    }
+
+    /// Effective options for one block: shared options, then the block's options,
+    /// then the options in its open tag.
+    pub fn options(&self, block_name: &str, open_tag_line: &str) -> HashMap<String, String> {
+        let mut opts: HashMap<String, String> = self.options.clone().into_iter().collect();
+        if let Some(block) = self.blocks.get(block_name) {
+            opts.extend(block.clone());
+        }
+        opts.extend(parse_tag_options(open_tag_line, block_name));
+        opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(s: &str) -> toml::Table {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_metadata() {
+        let config = Config::from_metadata(&table(
+            r#"
+[automd]
+author = "YONGQI"
+badges = ["version", "docs"]
+
+[automd.contributors]
+license = "MIT"
+"#,
+        ));
+        assert_eq!(config.options["author"], "YONGQI");
+        assert_eq!(config.blocks["badges"]["version"], "true");
+        assert_eq!(config.blocks["badges"]["docs"], "true");
+        assert_eq!(config.blocks["contributors"]["license"], "MIT");
+        assert_eq!(Config::from_metadata(&table("")), Config::default());
+    }
+
+    #[test]
+    fn test_merge_and_options() {
+        let mut config = Config::from_table(&table(
+            "author = \"ws\"\nlicense = \"MIT\"\nbadges = [\"version\"]",
+        ));
+        config.merge(Config::from_table(&table(
+            "author = \"pkg\"\n[badges]\ndocs = true",
+        )));
+        let opts = config.options("badges", "<!-- automdrs:badges version=false -->");
+        assert_eq!(opts["author"], "pkg");
+        assert_eq!(opts["license"], "MIT");
+        assert_eq!(opts["version"], "false");
+        assert_eq!(opts["docs"], "true");
+
+        let opts = config.options("contributors", "<!-- automdrs:contributors -->");
+        assert!(!opts.contains_key("docs"));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = std::env::temp_dir().join("automd_rs_test_config_file");
+        let _ = std::fs::create_dir_all(&dir);
+        assert_eq!(Config::find_file(&dir), None);
+        std::fs::write(
+            dir.join(".automdrs.toml"),
+            r#"
+files = ["README.md", "docs/index.md"]
+disabled = ["contributors"]
+
+[defaults]
+author = "A"
+badges = ["version"]
+
+[templates]
+cargo-install = "cargo binstall {{name}}"
+"#,
+        )
+        .unwrap();
+        let path = Config::find_file(&dir).unwrap();
+        assert!(path.ends_with(".automdrs.toml"));
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(
+            config.files,
+            [dir.join("README.md"), dir.join("docs/index.md")]
+        );
+        assert!(config.is_disabled("contributors"));
+        assert!(!config.is_disabled("badges"));
+        assert_eq!(config.options["author"], "A");
+        assert_eq!(config.blocks["badges"]["version"], "true");
+        assert_eq!(config.templates["cargo-install"], "cargo binstall {{name}}");
+
+        std::fs::write(dir.join("automd.toml"), "unknown = 1\n").unwrap();
+        assert!(Config::find_file(&dir).unwrap().ends_with("automd.toml"));
+        let err = Config::files(&dir).unwrap_err();
+        assert!(err.to_string().contains("unknown field"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,36 @@
+//! Unified diff between the current and the proposed README content.
+
+use similar::TextDiff;
+
+/// Renders a unified diff (3 lines of context) with `a/` and `b/` headers for `path`.
+/// Returns an empty string when `old` and `new` are identical.
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_identical() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "README.md"), "");
+    }
+
+    #[test]
+    fn test_unified_diff_changed() {
+        let out = unified_diff("a\nold\nc\n", "a\nnew\nc\n", "README.md");
+        assert!(out.starts_with("--- a/README.md\n+++ b/README.md\n"));
+        assert!(out.contains("@@ -1,3 +1,3 @@"));
+        assert!(out.contains("-old\n"));
+        assert!(out.contains("+new\n"));
+        assert!(out.contains(" a\n"));
+    }
+}
@@ -16,8 +16,61 @@ pub enum Error {
     #[error("Invalid repository URL: {0}")]
     InvalidRepoUrl(String),
 
+    #[error("Invalid config: {0}")]
+    Config(String),
+
+    #[error("package ID specification `{0}` did not match any packages")]
+    PackageNotFound(String),
+
+    #[error("Invalid file pattern: {0}")]
+    Pattern(String),
+
+    #[error("Invalid tag options at column {column}: {message}")]
+    TagOptions { column: usize, message: String },
+
     #[error("Block handler '{0}': {1}")]
     BlockHandler(String, String),
+
+    #[error("{} block(s) failed", .0.len())]
+    Blocks(Vec<BlockFailure>),
+
+    #[error("{} malformed block tag(s)", .0.len())]
+    MalformedBlocks(Vec<Diagnostic>),
+}
+
+/// A block whose generation failed; its existing content is left untouched.
+#[derive(Debug, Error)]
+#[error("line {line}: block '{name}': {source}")]
+pub struct BlockFailure {
+    pub name: String,
+    /// 1-based line number of the open tag.
+    pub line: usize,
+    pub source: Error,
+}
+
+/// A problem with the block tags of a README, with the offending source line.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{line}:{column}: {message}\n{line:>5} | {snippet}")]
+pub struct Diagnostic {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column where the tag starts.
+    pub column: usize,
+    pub message: String,
+    /// The offending line as written.
+    pub snippet: String,
+}
+
+impl Diagnostic {
+    pub fn new(line: usize, snippet: &str, message: String) -> Self {
+        let column = snippet.chars().take_while(|c| c.is_whitespace()).count() + 1;
+        Self {
+            line,
+            column,
+            message,
+            snippet: snippet.to_string(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,6 +1,8 @@
 //! Badge block generator: config + manifest → markdown lines.
 
+use crate::error::Result;
 use crate::parser::cargo::ParsedManifest;
+use crate::parser::repository::{HostKind, Repository};
 
 use log::trace;
 
@@ -14,7 +16,46 @@ pub struct BadgesConfig {
     pub repo_stars: bool,
 }
 
-pub fn generate(config: &BadgesConfig, manifest: &ParsedManifest) -> Vec<String> {
+/// Shields.io commit-activity badge; only GitHub provides one.
+fn commit_activity_badge(repo: &Repository) -> Option<String> {
+    match repo.host {
+        HostKind::GitHub => Some(format!(
+            "![GitHub commit activity](https://img.shields.io/github/commit-activity/m/{})",
+            repo.slug()
+        )),
+        _ => None,
+    }
+}
+
+/// Shields.io stars badge for GitHub, GitLab (incl. self-hosted) and Gitea/Codeberg.
+fn stars_badge(repo: &Repository) -> Option<String> {
+    match repo.host {
+        HostKind::GitHub => Some(format!(
+            "![GitHub Repo stars](https://img.shields.io/github/stars/{})",
+            repo.slug()
+        )),
+        HostKind::GitLab => Some(format!(
+            "![GitLab Stars](https://img.shields.io/gitlab/stars/{}?gitlab_url={})",
+            repo.slug().replace('/', "%2F"),
+            repo.host_url()
+        )),
+        HostKind::Codeberg | HostKind::Gitea => Some(format!(
+            "![{} Stars](https://img.shields.io/gitea/stars/{}?gitea_url={})",
+            repo.host.label(),
+            repo.slug(),
+            repo.host_url()
+        )),
+        HostKind::Other => None,
+    }
+}
+
+/// Crate badges only need `package.name`; repository badges error without `package.repository`
+/// and are skipped, with a message pushed to `warnings`, when the host has no such badge.
+pub fn generate(
+    config: &BadgesConfig,
+    manifest: &ParsedManifest,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>> {
     trace!("config: {:?}", config);
     trace!("manifest: {:?}", manifest);
     let n = config.version as usize
@@ -42,19 +83,28 @@ pub fn generate(config: &BadgesConfig, manifest: &ParsedManifest) -> Vec<String>
         ));
     }
     if config.commit_activity {
-        lines.push(format!(
-            "![GitHub commit activity](https://img.shields.io/github/commit-activity/m/{}/{})",
-            manifest.username, manifest.repository_name
-        ));
+        let repo = manifest.require_repository("badges", "commit_activity")?;
+        match commit_activity_badge(repo) {
+            Some(badge) => lines.push(badge),
+            None => warnings.push(format!(
+                "badges: commit_activity is not supported for {} repositories, skipped",
+                repo.host.label()
+            )),
+        }
     }
     if config.repo_stars {
-        lines.push(format!(
-            "![GitHub Repo stars](https://img.shields.io/github/stars/{}/{})",
-            manifest.username, manifest.repository_name
-        ));
+        let repo = manifest.require_repository("badges", "repo_stars")?;
+        match stars_badge(repo) {
+            Some(badge) => lines.push(badge),
+            None => warnings.push(format!(
+                "badges: repo_stars is not supported for {} ({}), skipped",
+                repo.domain,
+                repo.host.label()
+            )),
+        }
     }
     trace!("lines: {:?}", lines);
-    lines
+    Ok(lines)
 }
 
 #[cfg(test)]
@@ -62,19 +112,23 @@ mod tests {
     use super::*;
     use crate::parser::cargo::ParsedManifest;
 
-    fn manifest() -> ParsedManifest {
+    fn manifest_at(repository: &str) -> ParsedManifest {
         ParsedManifest {
             name: "my-crate".to_string(),
-            description: "d".to_string(),
-            username: "user".to_string(),
-            repository_name: "repo".to_string(),
+            description: Some("d".to_string()),
+            repository: Some(Repository::parse(repository).unwrap()),
+            ..Default::default()
         }
     }
 
+    fn manifest() -> ParsedManifest {
+        manifest_at("https://github.com/user/repo")
+    }
+
     #[test]
     fn test_generate_empty() {
         let config = BadgesConfig::default();
-        let out = generate(&config, &manifest());
+        let out = generate(&config, &manifest(), &mut Vec::new()).unwrap();
         assert_eq!(out, Vec::<String>::new());
     }
 
@@ -87,7 +141,7 @@ mod tests {
             commit_activity: false,
             repo_stars: true,
         };
-        let out = generate(&config, &manifest());
+        let out = generate(&config, &manifest(), &mut Vec::new()).unwrap();
         assert_eq!(out.len(), 3);
         assert!(out[0].contains("crates/v/my-crate"));
         assert!(out[1].contains("docsrs/my-crate"));
@@ -103,9 +157,80 @@ mod tests {
             commit_activity: true,
             repo_stars: false,
         };
-        let out = generate(&config, &manifest());
+        let out = generate(&config, &manifest(), &mut Vec::new()).unwrap();
         assert_eq!(out.len(), 2);
         assert!(out[0].contains("crates/d/my-crate"));
         assert!(out[1].contains("commit-activity/m/user/repo"));
     }
+
+    #[test]
+    fn test_generate_missing_repository() {
+        let manifest = ParsedManifest {
+            name: "my-crate".to_string(),
+            ..Default::default()
+        };
+        let config = BadgesConfig {
+            version: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate(&config, &manifest, &mut Vec::new()).unwrap().len(),
+            1
+        );
+        let config = BadgesConfig {
+            commit_activity: true,
+            ..Default::default()
+        };
+        let err = generate(&config, &manifest, &mut Vec::new()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("commit_activity requires package.repository")
+        );
+    }
+
+    #[test]
+    fn test_generate_other_hosts() {
+        let config = BadgesConfig {
+            commit_activity: true,
+            repo_stars: true,
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        let out = generate(
+            &config,
+            &manifest_at("https://gitlab.com/group/sub/repo"),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            [
+                "![GitLab Stars](https://img.shields.io/gitlab/stars/group%2Fsub%2Frepo?gitlab_url=https://gitlab.com)"
+            ]
+        );
+        let out = generate(
+            &config,
+            &manifest_at("https://codeberg.org/user/repo"),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            [
+                "![Codeberg Stars](https://img.shields.io/gitea/stars/user/repo?gitea_url=https://codeberg.org)"
+            ]
+        );
+        let out = generate(
+            &config,
+            &manifest_at("https://git.example.org/user/repo"),
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(out.is_empty());
+        assert_eq!(warnings.len(), 4);
+        assert_eq!(
+            warnings[3],
+            "badges: repo_stars is not supported for git.example.org (Git), skipped"
+        );
+    }
 }
@@ -1,6 +1,8 @@
-//! Contributors block generator: license, author, and contrib.rocks image.
+//! Contributors block generator: license, author, and contrib.rocks image (GitHub only).
 
+use crate::error::Result;
 use crate::parser::cargo::ParsedManifest;
+use crate::parser::repository::HostKind;
 
 use log::trace;
 
@@ -11,22 +13,41 @@ pub struct ContributorsConfig {
     pub license: String,
 }
 
-pub fn generate(config: &ContributorsConfig, manifest: &ParsedManifest) -> Vec<String> {
+/// The contrib.rocks image is GitHub-only; other hosts get a message in `warnings`.
+pub fn generate(
+    config: &ContributorsConfig,
+    manifest: &ParsedManifest,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>> {
     trace!("config: {:?}", config);
     trace!("manifest: {:?}", manifest);
-    vec![format!(
-        "Published under the [{license}](./LICENSE) license.\nMade by [@{author}](https://github.com/{username}) 💛\n<br><br>\n<a href=\"https://github.com/{username}/{repository_name}/graphs/contributors\">\n<img src=\"https://contrib.rocks/image?repo={username}/{repository_name}\" />\n</a>",
+    let repo = manifest.require_repository("contributors", "contributors graph")?;
+    let mut out = format!(
+        "Published under the [{license}](./LICENSE) license.\nMade by [@{author}]({owner_url}) 💛",
         author = config.author,
         license = config.license,
-        username = manifest.username,
-        repository_name = manifest.repository_name
-    )]
+        owner_url = repo.owner_url()
+    );
+    if repo.host == HostKind::GitHub {
+        out.push_str(&format!(
+            "\n<br><br>\n<a href=\"{web_url}/graphs/contributors\">\n<img src=\"https://contrib.rocks/image?repo={slug}\" />\n</a>",
+            web_url = repo.web_url,
+            slug = repo.slug()
+        ));
+    } else {
+        warnings.push(format!(
+            "contributors: contrib.rocks image is only available for GitHub repositories, skipped for {}",
+            repo.domain
+        ));
+    }
+    Ok(vec![out])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::cargo::ParsedManifest;
+    use crate::parser::repository::Repository;
 
     #[test]
     fn test_generate() {
@@ -36,14 +57,45 @@ mod tests {
         };
         let manifest = ParsedManifest {
             name: "automd-rs".to_string(),
-            description: "d".to_string(),
-            username: "betterhyq".to_string(),
-            repository_name: "automd-rs".to_string(),
+            description: Some("d".to_string()),
+            repository: Some(Repository::parse("https://github.com/betterhyq/automd-rs").unwrap()),
+            ..Default::default()
         };
-        let out = generate(&config, &manifest);
+        let out = generate(&config, &manifest, &mut Vec::new()).unwrap();
         assert_eq!(out.len(), 1);
         assert!(out[0].contains("YONGQI"));
         assert!(out[0].contains("MIT"));
         assert!(out[0].contains("betterhyq/automd-rs"));
     }
+
+    #[test]
+    fn test_generate_missing_repository() {
+        let manifest = ParsedManifest {
+            name: "automd-rs".to_string(),
+            ..Default::default()
+        };
+        assert!(generate(&ContributorsConfig::default(), &manifest, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_generate_non_github() {
+        let config = ContributorsConfig {
+            author: "A".to_string(),
+            license: "MIT".to_string(),
+        };
+        let manifest = ParsedManifest {
+            name: "x".to_string(),
+            repository: Some(Repository::parse("https://gitlab.com/group/sub/x").unwrap()),
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        let out = generate(&config, &manifest, &mut warnings).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            out,
+            [
+                "Published under the [MIT](./LICENSE) license.\nMade by [@A](https://gitlab.com/group) 💛"
+            ]
+        );
+    }
 }
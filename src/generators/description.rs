@@ -1,14 +1,15 @@
 //! Description block generator.
 
+use crate::error::Result;
 use crate::parser::cargo::ParsedManifest;
 
 use log::{info, trace};
 
-pub fn generate(manifest: &ParsedManifest) -> Vec<String> {
+pub fn generate(manifest: &ParsedManifest) -> Result<Vec<String>> {
     trace!("config: {:?}", manifest);
     info!("cargo-install config: {:?}", manifest);
-    vec![format!(
+    Ok(vec![format!(
         "\n{description}\n",
-        description = manifest.description
-    )]
+        description = manifest.require_description("description")?
+    )])
 }
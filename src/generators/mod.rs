@@ -6,4 +6,5 @@ pub mod cargo_install;
 pub mod contributors;
 pub mod description;
 pub mod file;
+pub mod template;
 pub mod with_automdrs;
\ No newline at end of file
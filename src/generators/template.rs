@@ -0,0 +1,79 @@
+//! Template block generator: a user template from automd.toml with `{{placeholder}}` substitution.
+
+use crate::parser::cargo::ParsedManifest;
+use std::collections::HashMap;
+
+use log::trace;
+
+/// Value for a placeholder: block options first, then package fields.
+fn lookup(key: &str, manifest: &ParsedManifest, opts: &HashMap<String, String>) -> Option<String> {
+    if let Some(value) = opts.get(key) {
+        return Some(value.clone());
+    }
+    match key {
+        "name" => Some(manifest.name.clone()),
+        "version" => manifest.version.clone(),
+        "edition" => manifest.edition.clone(),
+        "rust_version" => manifest.rust_version.clone(),
+        "description" => manifest.description.clone(),
+        "repository" => manifest.repository.as_ref().map(|r| r.web_url.clone()),
+        "homepage" => manifest.homepage.clone(),
+        "documentation" => manifest.documentation.clone(),
+        "license" => manifest.license.clone(),
+        _ => None,
+    }
+}
+
+/// Renders `template` line by line; unknown or unset placeholders are left as written.
+pub fn generate(
+    template: &str,
+    manifest: &ParsedManifest,
+    opts: &HashMap<String, String>,
+) -> Vec<String> {
+    trace!("template: {:?}", template);
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + 2 + len].trim();
+        out.push_str(&rest[..start]);
+        match lookup(key, manifest, opts) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + len + 4]),
+        }
+        rest = &rest[start + len + 4..];
+    }
+    out.push_str(rest);
+    out.lines().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let manifest = ParsedManifest {
+            name: "my-crate".to_string(),
+            version: Some("1.2.0".to_string()),
+            ..Default::default()
+        };
+        let opts: HashMap<String, String> = [("tool".to_string(), "binstall".to_string())].into();
+        let out = generate(
+            "```sh\ncargo {{ tool }} {{name}}@{{version}}\n```\n{{license}} {{",
+            &manifest,
+            &opts,
+        );
+        assert_eq!(
+            out,
+            [
+                "```sh",
+                "cargo binstall my-crate@1.2.0",
+                "```",
+                "{{license}} {{"
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Block dispatcher: parses tag options, routes to generators, fills block body.
 
+use crate::config::Config;
 use crate::error::Result;
 use crate::generators::badges::{self as badges_gen, BadgesConfig};
 use crate::generators::cargo_add::{self as cargo_add_gen};
@@ -9,8 +10,10 @@ use crate::generators::with_automdrs::{self as with_automdrs_gen, WithAutomdrsCo
 use crate::generators::description::{self as description_gen};
 use crate::generators::file::{self as file_gen};
 use crate::parser::cargo::ParsedManifest;
-use crate::parser::tag_options::{option_bool, parse_tag_options};
-use log::{trace, info};
+use crate::parser::repository::HostKind;
+use crate::parser::tag_options::option_bool;
+use log::{trace, info, warn};
+use std::collections::HashMap;
 
 /// Context passed to block handlers (parsed Cargo.toml).
 #[derive(Debug, Clone)]
@@ -18,11 +21,31 @@ pub struct UpdateContext {
     pub config: ParsedManifest,
     /// Crate root directory for resolving relative paths (e.g. in `file` block).
     pub manifest_dir: std::path::PathBuf,
+    /// Block option defaults from `[package.metadata.automd]` / `[workspace.metadata.automd]`.
+    pub automd: Config,
 }
 
 impl UpdateContext {
     pub fn new(config: ParsedManifest, manifest_dir: std::path::PathBuf) -> Self {
-        Self { config, manifest_dir }
+        Self {
+            config,
+            manifest_dir,
+            automd: Config::default(),
+        }
+    }
+
+    /// Sets the option defaults that tag options are merged over. A shared `host` option
+    /// (e.g. `host = "gitea"`) overrides the code host detected from `package.repository`.
+    pub fn with_automd(mut self, automd: Config) -> Self {
+        if let Some(name) = automd.options.get("host") {
+            match (HostKind::from_name(name), self.config.repository.as_mut()) {
+                (Some(host), Some(repository)) => repository.set_host(host),
+                (Some(_), None) => {}
+                (None, _) => warn!("automd.host: unknown host '{}', ignored", name),
+            }
+        }
+        self.automd = automd;
+        self
     }
 }
 
@@ -34,22 +57,33 @@ pub trait BlockHandler: Send + Sync {
         open_tag_line: &str,
         context: &UpdateContext,
     ) -> Result<Vec<String>>;
+
+    /// Like [`generate`](BlockHandler::generate), but pushes non-fatal problems (e.g. a badge
+    /// the host does not support) onto `warnings` for the run report. Defaults to `generate`.
+    fn generate_with_warnings(
+        &self,
+        block_name: &str,
+        open_tag_line: &str,
+        context: &UpdateContext,
+        warnings: &mut Vec<String>,
+    ) -> Result<Vec<String>> {
+        let _ = warnings;
+        self.generate(block_name, open_tag_line, context)
+    }
 }
 
-fn parse_badges_config(open_tag: &str) -> BadgesConfig {
-    let opts = parse_tag_options(open_tag, "badges");
+fn parse_badges_config(opts: &HashMap<String, String>) -> BadgesConfig {
     trace!("badges config: {:?}", opts);
     BadgesConfig {
-        version: option_bool(&opts, &["showCrateVersion", "version"]),
-        downloads: option_bool(&opts, &["showCrateDownloads", "downloads"]),
-        docs: option_bool(&opts, &["showCrateDocs", "docs"]),
-        commit_activity: option_bool(&opts, &["showCommitActivity", "commit_activity"]),
-        repo_stars: option_bool(&opts, &["showRepoStars", "repo_stars"]),
+        version: option_bool(opts, &["showCrateVersion", "version"]),
+        downloads: option_bool(opts, &["showCrateDownloads", "downloads"]),
+        docs: option_bool(opts, &["showCrateDocs", "docs"]),
+        commit_activity: option_bool(opts, &["showCommitActivity", "commit_activity"]),
+        repo_stars: option_bool(opts, &["showRepoStars", "repo_stars"]),
     }
 }
 
-fn parse_contributors_config(open_tag: &str) -> ContributorsConfig {
-    let opts = parse_tag_options(open_tag, "contributors");
+fn parse_contributors_config(opts: &HashMap<String, String>) -> ContributorsConfig {
     trace!("contributors config: {:?}", opts);
     ContributorsConfig {
         author: opts.get("author").cloned().unwrap_or_default(),
@@ -57,8 +91,7 @@ fn parse_contributors_config(open_tag: &str) -> ContributorsConfig {
     }
 }
 
-fn parse_with_automdrs_config(open_tag: &str) -> WithAutomdrsConfig {
-    let opts = parse_tag_options(open_tag, "with-automdrs");
+fn parse_with_automdrs_config(opts: &HashMap<String, String>) -> WithAutomdrsConfig {
     trace!("with-automdrs config: {:?}", opts);
     WithAutomdrsConfig {
         message: opts.get("message").cloned().unwrap_or_default(),
@@ -76,20 +109,34 @@ impl BlockHandler for DefaultHandler {
         open_tag_line: &str,
         context: &UpdateContext,
     ) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        let lines = self.generate_with_warnings(block_name, open_tag_line, context, &mut warnings);
+        warnings.iter().for_each(|w| warn!("{}", w));
+        lines
+    }
+
+    fn generate_with_warnings(
+        &self,
+        block_name: &str,
+        open_tag_line: &str,
+        context: &UpdateContext,
+        warnings: &mut Vec<String>,
+    ) -> Result<Vec<String>> {
+        let opts = context.automd.options(block_name, open_tag_line);
         match block_name {
             "badges" => {
                 trace!("parsing badges config");
-                let config = parse_badges_config(open_tag_line);
-                Ok(badges_gen::generate(&config, &context.config))
+                let config = parse_badges_config(&opts);
+                badges_gen::generate(&config, &context.config, warnings)
             }
             "contributors" => {
                 trace!("parsing contributors config");
-                let config = parse_contributors_config(open_tag_line);
-                Ok(contributors_gen::generate(&config, &context.config))
+                let config = parse_contributors_config(&opts);
+                contributors_gen::generate(&config, &context.config, warnings)
             }
             "with-automdrs" => {
                 trace!("parsing with-automdrs config");
-                let config = parse_with_automdrs_config(open_tag_line);
+                let config = parse_with_automdrs_config(&opts);
                 Ok(with_automdrs_gen::generate(&config))
             }
             "cargo-install" => {
@@ -102,11 +149,10 @@ impl BlockHandler for DefaultHandler {
             }
             "description" => {
                 trace!("parsing description config");
-                Ok(description_gen::generate(&context.config))
+                description_gen::generate(&context.config)
             }
             "file" => {
                 info!("parsing file config");
-                let opts = parse_tag_options(open_tag_line, "file");
                 let src = opts.get("src").cloned().unwrap_or_default();
                 Ok(file_gen::generate(&context.manifest_dir, &src)?)
             }
@@ -119,14 +165,15 @@ impl BlockHandler for DefaultHandler {
 mod tests {
     use super::*;
     use crate::parser::cargo::ParsedManifest;
+    use crate::parser::repository::Repository;
 
     fn context() -> UpdateContext {
         UpdateContext::new(
             ParsedManifest {
                 name: "test-crate".to_string(),
-                description: "d".to_string(),
-                username: "u".to_string(),
-                repository_name: "r".to_string(),
+                description: Some("d".to_string()),
+                repository: Some(Repository::parse("https://github.com/u/r").unwrap()),
+                ..Default::default()
             },
             std::path::PathBuf::from("."),
         )
@@ -167,6 +214,64 @@ mod tests {
         assert!(out[0].contains("MIT"));
     }
 
+    #[test]
+    fn test_generate_with_metadata_defaults() {
+        let metadata: toml::Table =
+            "[automd]\nauthor = \"A\"\nbadges = [\"version\", \"docs\"]\n[automd.contributors]\nlicense = \"MIT\""
+                .parse()
+                .unwrap();
+        let ctx = context().with_automd(Config::from_metadata(&metadata));
+        let h = DefaultHandler;
+        let out = h
+            .generate("badges", "<!-- automdrs:badges docs=false -->", &ctx)
+            .unwrap();
+        assert_eq!(out.len(), 1);
+        assert!(out[0].contains("crates/v/test-crate"));
+
+        let out = h
+            .generate(
+                "contributors",
+                "<!-- automdrs:contributors license=\"Apache-2.0\" -->",
+                &ctx,
+            )
+            .unwrap();
+        assert!(out[0].contains("[@A]"));
+        assert!(out[0].contains("Apache-2.0"));
+    }
+
+    #[test]
+    fn test_with_automd_host_override() {
+        let mut ctx = context();
+        ctx.config.repository =
+            Some(Repository::parse("https://git.example.com:3000/me/tool").unwrap());
+        let metadata: toml::Table = "[automd]\nhost = \"gitea\"".parse().unwrap();
+        let ctx = ctx.with_automd(Config::from_metadata(&metadata));
+        let repository = ctx.config.repository.as_ref().unwrap();
+        assert_eq!(repository.host, HostKind::Gitea);
+        let out = DefaultHandler
+            .generate("badges", "<!-- automdrs:badges repo_stars -->", &ctx)
+            .unwrap();
+        assert_eq!(
+            out,
+            ["![Gitea Stars](https://img.shields.io/gitea/stars/me/tool?gitea_url=https://git.example.com:3000)"]
+        );
+    }
+
+    #[test]
+    fn test_generate_records_warnings() {
+        let mut ctx = context();
+        ctx.config.repository = Some(Repository::parse("https://gitlab.com/u/r").unwrap());
+        let h = DefaultHandler;
+        let tag = "<!-- automdrs:badges commit_activity -->";
+        let mut warnings = Vec::new();
+        let lines = h
+            .generate_with_warnings("badges", tag, &ctx, &mut warnings)
+            .unwrap();
+        assert_eq!(lines, h.generate("badges", tag, &ctx).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("commit_activity"));
+    }
+
     #[test]
     fn test_generate_with_automdrs() {
         let h = DefaultHandler::default();
@@ -196,9 +301,7 @@ mod tests {
         let ctx = UpdateContext::new(
             ParsedManifest {
                 name: "test".to_string(),
-                description: "d".to_string(),
-                username: "u".to_string(),
-                repository_name: "r".to_string(),
+                ..Default::default()
             },
             manifest_dir.to_path_buf(),
         );
@@ -1,56 +1,248 @@
 //! Update README blocks from Cargo.toml; handler dispatches by block name to generators.
 
+/// Shared by the `automd-rs` and `cargo-automd` binaries; not part of the library API.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod cli;
+pub mod config;
+pub mod diff;
 pub mod error;
 pub mod generators;
 pub mod handler;
 pub mod parser;
+pub mod report;
+pub mod targets;
+pub mod watch;
+pub mod write;
 
-pub use error::{Error, Result};
+pub use config::Config;
+pub use error::{BlockFailure, Diagnostic, Error, Result};
 pub use handler::{BlockHandler, DefaultHandler, UpdateContext};
-pub use parser::cargo::{ParsedManifest, parse as parse_manifest};
+pub use parser::cargo::{Dependency, ParsedManifest, Readme, Target, parse as parse_manifest};
 pub use parser::readme::{
-    BlockRequest, assign_and_generate, parse_readme_blocks, replace_blocks_once, update_readme,
+    BlockRequest, assign_and_generate, assign_and_generate_keep_going, changed_blocks,
+    generate_blocks, parse_readme_blocks, replace_blocks_once, update_readme, validate_blocks,
 };
+pub use parser::repository::{HostKind, Repository};
+pub use parser::workspace::{
+    WorkspaceMember, find_root as find_workspace_root, members as workspace_members,
+    select as select_packages,
+};
+pub use report::{BlockReport, RunReport};
 
 use log::trace;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Options shared by [`run_with_options`], [`check_with_options`] and [`dry_run_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Collect block errors instead of stopping at the first one: failed blocks keep their
+    /// content and the rest are updated. Runs and dry runs list the failures in the report
+    /// (see [`RunReport::failed_blocks`]); checks return them as [`Error::Blocks`].
+    pub keep_going: bool,
+    /// Keep the previous content in `README.md.bak` when the file is rewritten.
+    pub backup: bool,
+}
 
-pub fn run(manifest_dir: &Path, readme_path: &Path) -> Result<String> {
-    run_with_handler(manifest_dir, readme_path, &DefaultHandler::default())
+pub fn run(manifest_dir: &Path, readme_path: &Path) -> Result<RunReport> {
+    run_with_handler(manifest_dir, readme_path, &DefaultHandler)
 }
 
 /// Run with custom handler: parse Cargo.toml → parse README → generate per block → replace once.
+/// Malformed block tags are reported as [`Error::MalformedBlocks`] before anything is written,
+/// and the file is only written when its content changes.
 pub fn run_with_handler(
     manifest_dir: &Path,
     readme_path: &Path,
     handler: &dyn BlockHandler,
-) -> Result<String> {
-    let config = parser::cargo::parse(manifest_dir)?;
-    trace!("config: {:?}", config);
+) -> Result<RunReport> {
+    run_with_options(manifest_dir, readme_path, handler, &RunOptions::default())
+}
 
-    let readme_content = std::fs::read_to_string(readme_path)?;
-    trace!("readme_content: {:?}", readme_content);
+/// Run with custom handler and options. With `keep_going`, the README is written with every
+/// block that succeeded and the report still lists the blocks that failed.
+pub fn run_with_options(
+    manifest_dir: &Path,
+    readme_path: &Path,
+    handler: &dyn BlockHandler,
+    options: &RunOptions,
+) -> Result<RunReport> {
+    let pipeline = Pipeline::run(manifest_dir, readme_path, handler, options)?;
+    let modified = pipeline.updated != pipeline.readme_content;
+    if modified {
+        write::write_atomic(readme_path, &pipeline.updated, options.backup)?;
+    }
+    trace!("modified: {:?}", modified);
+    Ok(RunReport {
+        path: readme_path.to_path_buf(),
+        updated: pipeline.updated,
+        modified,
+        blocks: pipeline.blocks,
+    })
+}
 
-    let context = UpdateContext::new(config, manifest_dir.to_path_buf());
-    trace!("context: {:?}", context);
+/// Runs every target file with the same manifest. `patterns` are paths or globs such as
+/// `docs/**/*.md` (see [`targets::expand`]); `file` block paths are still resolved against
+/// `manifest_dir`. A bad pattern fails the whole call, while each file gets its own result.
+pub fn run_files(
+    manifest_dir: &Path,
+    patterns: &[PathBuf],
+    handler: &dyn BlockHandler,
+    options: &RunOptions,
+) -> Result<Vec<(PathBuf, Result<RunReport>)>> {
+    Ok(targets::expand(patterns)?
+        .into_iter()
+        .map(|path| {
+            let report = run_with_options(manifest_dir, &path, handler, options);
+            (path, report)
+        })
+        .collect())
+}
 
-    let requests = parser::readme::parse_readme_blocks(&readme_content);
-    trace!("requests: {:?}", requests);
+/// Check mode: runs the same pipeline in memory and returns the blocks that would change.
+/// The README is never written; an empty result means it is up to date.
+pub fn check(manifest_dir: &Path, readme_path: &Path) -> Result<Vec<BlockRequest>> {
+    check_with_handler(manifest_dir, readme_path, &DefaultHandler)
+}
 
-    let generated = parser::readme::assign_and_generate(&requests, handler, &context)?;
-    trace!("generated: {:?}", generated);
+/// Check mode with custom handler.
+pub fn check_with_handler(
+    manifest_dir: &Path,
+    readme_path: &Path,
+    handler: &dyn BlockHandler,
+) -> Result<Vec<BlockRequest>> {
+    check_with_options(manifest_dir, readme_path, handler, &RunOptions::default())
+}
+
+/// Check mode with custom handler and options.
+pub fn check_with_options(
+    manifest_dir: &Path,
+    readme_path: &Path,
+    handler: &dyn BlockHandler,
+    options: &RunOptions,
+) -> Result<Vec<BlockRequest>> {
+    let mut pipeline = Pipeline::run(manifest_dir, readme_path, handler, options)?;
+    let changed: Vec<BlockRequest> = parser::readme::changed_blocks(
+        &pipeline.readme_content,
+        &pipeline.requests,
+        &pipeline.generated,
+    )
+    .into_iter()
+    .cloned()
+    .collect();
+    trace!("changed: {:?}", changed);
+    pipeline.take_failures()?;
+    Ok(changed)
+}
 
-    let updated = parser::readme::replace_blocks_once(&readme_content, &generated);
-    trace!("updated: {:?}", updated);
+/// Proposed README content and its unified diff against the file on disk.
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    pub updated: String,
+    /// Empty when nothing would change.
+    pub diff: String,
+    /// Every block in document order, as [`RunReport::blocks`].
+    pub blocks: Vec<BlockReport>,
+}
 
-    std::fs::write(readme_path, &updated)?;
-    Ok(updated)
+/// Dry run: computes the updated README and a unified diff without writing it.
+pub fn dry_run(manifest_dir: &Path, readme_path: &Path) -> Result<DryRun> {
+    dry_run_with_handler(manifest_dir, readme_path, &DefaultHandler)
+}
+
+/// Dry run with custom handler.
+pub fn dry_run_with_handler(
+    manifest_dir: &Path,
+    readme_path: &Path,
+    handler: &dyn BlockHandler,
+) -> Result<DryRun> {
+    dry_run_with_options(manifest_dir, readme_path, handler, &RunOptions::default())
+}
+
+/// Dry run with custom handler and options.
+pub fn dry_run_with_options(
+    manifest_dir: &Path,
+    readme_path: &Path,
+    handler: &dyn BlockHandler,
+    options: &RunOptions,
+) -> Result<DryRun> {
+    let pipeline = Pipeline::run(manifest_dir, readme_path, handler, options)?;
+    let diff = diff::unified_diff(
+        &pipeline.readme_content,
+        &pipeline.updated,
+        &readme_path.to_string_lossy(),
+    );
+    trace!("diff: {:?}", diff);
+    Ok(DryRun {
+        updated: pipeline.updated,
+        diff,
+        blocks: pipeline.blocks,
+    })
+}
+
+/// In-memory result of one parse → generate → replace pass over a README.
+struct Pipeline {
+    readme_content: String,
+    requests: Vec<BlockRequest>,
+    generated: Vec<Vec<String>>,
+    blocks: Vec<BlockReport>,
+    updated: String,
+    /// Blocks that failed with `keep_going`; always empty otherwise.
+    failures: Vec<BlockFailure>,
+}
+
+impl Pipeline {
+    fn run(
+        manifest_dir: &Path,
+        readme_path: &Path,
+        handler: &dyn BlockHandler,
+        options: &RunOptions,
+    ) -> Result<Self> {
+        let config = parser::cargo::parse(manifest_dir)?;
+        trace!("config: {:?}", config);
+
+        let readme_content = std::fs::read_to_string(readme_path)?;
+        trace!("readme_content: {:?}", readme_content);
+        parser::readme::ensure_well_formed(&readme_content)?;
+
+        let automd = Config::load(manifest_dir, &config)?;
+        let context = UpdateContext::new(config, manifest_dir.to_path_buf()).with_automd(automd);
+        trace!("context: {:?}", context);
+
+        let requests = parser::readme::parse_readme_blocks(&readme_content);
+        trace!("requests: {:?}", requests);
+
+        let (blocks, failures) =
+            parser::readme::generate_blocks(&requests, handler, &context, options.keep_going)?;
+        let generated: Vec<Vec<String>> = blocks.iter().map(|b| b.new_body.clone()).collect();
+        trace!("generated: {:?}", generated);
+        trace!("failures: {:?}", failures);
+
+        let updated = parser::readme::replace_blocks_once(&readme_content, &generated);
+        trace!("updated: {:?}", updated);
+
+        Ok(Self {
+            readme_content,
+            requests,
+            generated,
+            blocks,
+            updated,
+            failures,
+        })
+    }
+
+    /// [`Error::Blocks`] with the collected failures, if any block failed.
+    fn take_failures(&mut self) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Blocks(std::mem::take(&mut self.failures)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
 
     #[test]
     fn test_run_with_handler() {
@@ -74,10 +266,37 @@ repository = "https://github.com/foo/bar.git"
             "Title\n\n<!-- automdrs:badges version -->\n<!-- /automdrs -->\n",
         )
         .unwrap();
-        let result = run_with_handler(&dir, &readme, &crate::handler::DefaultHandler::default());
-        let out = result.unwrap();
-        assert!(out.contains("crates/v/test-pkg"));
-        assert!(out.contains("<!-- automdrs:badges version -->"));
+        let result = run_with_handler(&dir, &readme, &crate::handler::DefaultHandler);
+        let report = result.unwrap();
+        assert!(report.modified);
+        assert_eq!(report.blocks.len(), 1);
+        let block = &report.blocks[0];
+        assert_eq!(block.name, "badges");
+        assert_eq!((block.start_line, block.end_line), (3, Some(4)));
+        assert_eq!(
+            block.options.get("version").map(String::as_str),
+            Some("true")
+        );
+        assert!(block.old_body.is_empty());
+        assert_eq!(
+            block.new_body,
+            ["![Crates.io Version](https://img.shields.io/crates/v/test-pkg)"]
+        );
+        assert!(block.changed);
+        assert!(block.warnings.is_empty());
+        assert_eq!(block.error, None);
+        assert!(report.updated.contains("crates/v/test-pkg"));
+        assert!(report.updated.contains("<!-- automdrs:badges version -->"));
+        assert_eq!(std::fs::read_to_string(&readme).unwrap(), report.updated);
+
+        let mtime = std::fs::metadata(&readme).unwrap().modified().unwrap();
+        let report = run_with_handler(&dir, &readme, &crate::handler::DefaultHandler).unwrap();
+        assert!(!report.modified);
+        assert_eq!(report.changed_blocks().count(), 0);
+        assert_eq!(
+            std::fs::metadata(&readme).unwrap().modified().unwrap(),
+            mtime
+        );
         let _ = std::fs::remove_dir_all(&dir);
     }
 
@@ -105,8 +324,184 @@ repository = "https://github.com/a/b.git"
         .unwrap();
         let result = run(&dir, &readme);
         assert!(result.is_ok());
-        let out = result.unwrap();
-        assert!(out.contains("automd-rs"));
+        let report = result.unwrap();
+        assert!(report.updated.contains("automd-rs"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_check() {
+        let dir = std::env::temp_dir().join("automd_rs_test_check");
+        let _ = std::fs::create_dir_all(&dir);
+        let cargo_toml = dir.join("Cargo.toml");
+        let readme = dir.join("README.md");
+        std::fs::write(
+            &cargo_toml,
+            r#"
+[package]
+name = "check-pkg"
+version = "0.1.0"
+description = "d"
+repository = "https://github.com/a/b.git"
+"#,
+        )
+        .unwrap();
+        let stale = "Hi\n<!-- automdrs:badges version -->\n<!-- /automdrs -->\n";
+        std::fs::write(&readme, stale).unwrap();
+        let changed = check(&dir, &readme).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].name, "badges");
+        assert_eq!(changed[0].line, 2);
+        assert_eq!(std::fs::read_to_string(&readme).unwrap(), stale);
+
+        run(&dir, &readme).unwrap();
+        assert!(check(&dir, &readme).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let dir = std::env::temp_dir().join("automd_rs_test_dry_run");
+        let _ = std::fs::create_dir_all(&dir);
+        let cargo_toml = dir.join("Cargo.toml");
+        let readme = dir.join("README.md");
+        std::fs::write(
+            &cargo_toml,
+            r#"
+[package]
+name = "dry-pkg"
+version = "0.1.0"
+description = "d"
+repository = "https://github.com/a/b.git"
+"#,
+        )
+        .unwrap();
+        let original = "Hi\n<!-- automdrs:badges version -->\n<!-- /automdrs -->";
+        std::fs::write(&readme, original).unwrap();
+        let out = dry_run(&dir, &readme).unwrap();
+        assert!(out.updated.contains("crates/v/dry-pkg"));
+        assert!(
+            out.diff
+                .contains("+![Crates.io Version](https://img.shields.io/crates/v/dry-pkg)")
+        );
+        assert_eq!(std::fs::read_to_string(&readme).unwrap(), original);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_run_keep_going() {
+        let dir = std::env::temp_dir().join("automd_rs_test_keep_going");
+        let _ = std::fs::create_dir_all(&dir);
+        let readme = dir.join("README.md");
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"kg-pkg\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        let original = "<!-- automdrs:file src=\"missing.md\" -->\nkept\n<!-- /automdrs -->\n<!-- automdrs:cargo-add -->\n<!-- /automdrs -->";
+        std::fs::write(&readme, original).unwrap();
+
+        assert!(run(&dir, &readme).is_err());
+        assert_eq!(std::fs::read_to_string(&readme).unwrap(), original);
+
+        let options = RunOptions {
+            keep_going: true,
+            ..Default::default()
+        };
+        let report = run_with_options(&dir, &readme, &DefaultHandler, &options).unwrap();
+        assert!(report.modified);
+        let failures: Vec<&BlockReport> = report.failed_blocks().collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            (failures[0].name.as_str(), failures[0].start_line),
+            ("file", 1)
+        );
+        assert!(failures[0].error.as_ref().unwrap().contains("missing.md"));
+        assert_eq!(failures[0].new_body, ["kept"]);
+        let written = std::fs::read_to_string(&readme).unwrap();
+        assert_eq!(written, report.updated);
+        assert!(written.contains("\nkept\n"));
+        assert!(written.contains("cargo add kg-pkg"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_run_invalid_repository() {
+        let dir = std::env::temp_dir().join("automd_rs_test_invalid_repository");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"bad-repo\"\nrepository = \"not a url\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("snippet.txt"), "hello\n").unwrap();
+        let readme = dir.join("README.md");
+        std::fs::write(
+            &readme,
+            "<!-- automdrs:file src=\"snippet.txt\" -->\n<!-- /automdrs -->\n<!-- automdrs:cargo-add -->\n<!-- /automdrs -->\n",
+        )
+        .unwrap();
+        let report = run(&dir, &readme).unwrap();
+        assert!(report.modified);
+        assert!(report.updated.contains("hello"));
+        assert!(report.updated.contains("cargo add bad-repo"));
+
+        std::fs::write(
+            &readme,
+            "<!-- automdrs:badges commit_activity -->\n<!-- /automdrs -->\n",
+        )
+        .unwrap();
+        let err = run(&dir, &readme).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("requires a valid package.repository"),
+            "{err}"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_run_files() {
+        let dir = std::env::temp_dir().join("automd_rs_test_run_files");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"files-pkg\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+        let block = "<!-- automdrs:file src=\"src/lib.rs\" -->\n<!-- /automdrs -->\n";
+        std::fs::write(dir.join("docs/a.md"), block).unwrap();
+        std::fs::write(dir.join("docs/b.md"), block.replace("lib.rs", "nope.rs")).unwrap();
+
+        let results = run_files(
+            &dir,
+            &[dir.join("docs/*.md")],
+            &DefaultHandler,
+            &RunOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, dir.join("docs/a.md"));
+        assert!(results[0].1.as_ref().unwrap().modified);
+        assert!(
+            std::fs::read_to_string(dir.join("docs/a.md"))
+                .unwrap()
+                .contains("pub fn f() {}")
+        );
+        assert!(results[1].1.is_err());
+        assert!(
+            run_files(
+                &dir,
+                &[dir.join("*.txt")],
+                &DefaultHandler,
+                &RunOptions::default()
+            )
+            .is_err()
+        );
         let _ = std::fs::remove_dir_all(&dir);
     }
 }
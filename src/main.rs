@@ -1,18 +1,12 @@
-//! CLI entry point: run from current directory (Cargo.toml + README.md).
+//! CLI entry point: parse arguments, set up logging, and run (see `automd_rs::cli`).
 
-use log::{trace, warn};
-use std::path::Path;
+use automd_rs::cli::{self, Cli};
+use clap::Parser;
+use std::process::ExitCode;
 
-fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
-    let manifest_dir = Path::new(".");
-    let readme_path = Path::new("README.md");
-    trace!("manifest_dir: {:?}", manifest_dir);
-    trace!("readme_path: {:?}", readme_path);
-
-    if let Err(e) = automd_rs::run(manifest_dir, readme_path) {
-        warn!("error: {}", e);
-        std::process::exit(1);
-    }
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(cli.log_filter()))
+        .init();
+    cli::run(&cli)
 }
@@ -1,49 +1,198 @@
-//! Cargo.toml parsing: manifest path → name, username, repository_name.
+//! Cargo.toml parsing: manifest path → package fields, targets, features, dependencies, metadata.
 
 use crate::error::{Error, Result};
+use crate::parser::repository::Repository;
+use crate::parser::workspace::{Inheritable, InheritedPackage, WorkspacePackage};
 use c12_parser::{FormatOptions, Formatted, parse_toml};
 use log::trace;
 use serde::Deserialize;
-use std::path::Path;
-use url::Url;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-/// Parsed package metadata from Cargo.toml.
-#[derive(Debug, Clone)]
+/// Parsed package metadata from Cargo.toml, with workspace-inherited fields resolved.
+/// Optional fields are only required by the blocks that render them.
+#[derive(Debug, Clone, Default)]
 pub struct ParsedManifest {
     pub name: String,
-    pub description: String,
-    pub username: String,
-    pub repository_name: String,
+    pub version: Option<String>,
+    pub edition: Option<String>,
+    pub rust_version: Option<String>,
+    pub description: Option<String>,
+    pub repository: Option<Repository>,
+    /// Why `package.repository` could not be parsed; only blocks that need it fail.
+    pub repository_error: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    pub license: Option<String>,
+    pub license_file: Option<String>,
+    pub readme: Option<Readme>,
+    pub authors: Vec<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    /// `[features]`: feature name → enabled features/dependencies.
+    pub features: BTreeMap<String, Vec<String>>,
+    pub dependencies: BTreeMap<String, Dependency>,
+    pub dev_dependencies: BTreeMap<String, Dependency>,
+    pub build_dependencies: BTreeMap<String, Dependency>,
+    pub lib: Option<Target>,
+    pub bins: Vec<Target>,
+    pub examples: Vec<Target>,
+    /// `[package.metadata]`, untyped.
+    pub metadata: toml::Table,
+}
+
+/// `package.readme`: a path relative to the manifest, or `true` / `false` to use the default or disable it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Readme {
+    Path(String),
+    Enabled(bool),
+}
+
+impl Readme {
+    /// README file of the crate in `manifest_dir`: the path set in `readme`, `README.md` for
+    /// `true`, `None` for `false`. When `readme` is unset, `README.md` is only used if it
+    /// exists, as cargo does.
+    pub fn resolve(readme: Option<&Readme>, manifest_dir: &Path) -> Option<PathBuf> {
+        match readme {
+            None => Some(manifest_dir.join("README.md")).filter(|path| path.is_file()),
+            Some(Readme::Enabled(true)) => Some(manifest_dir.join("README.md")),
+            Some(Readme::Enabled(false)) => None,
+            Some(Readme::Path(path)) => Some(manifest_dir.join(path)),
+        }
+    }
+}
+
+/// A dependency entry; the `"1.0"` shorthand becomes `version: Some("1.0")`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Dependency {
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub git: Option<String>,
+    /// Renamed dependency: the actual package name.
+    pub package: Option<String>,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    pub default_features: Option<bool>,
+    /// `dep.workspace = true`: the rest is defined in `[workspace.dependencies]`.
+    #[serde(default)]
+    pub workspace: bool,
+}
+
+/// A `[lib]`, `[[bin]]` or `[[example]]` target.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Target {
+    pub name: Option<String>,
+    pub path: Option<String>,
+    #[serde(default)]
+    pub required_features: Vec<String>,
+}
+
+impl ParsedManifest {
+    /// README to update for this crate, from `package.readme` (see [`Readme::resolve`]).
+    pub fn readme_path(&self, manifest_dir: &Path) -> Option<PathBuf> {
+        Readme::resolve(self.readme.as_ref(), manifest_dir)
+    }
+
+    /// Returns the description, or a `block` error when `package.description` is missing.
+    pub fn require_description(&self, block: &str) -> Result<&str> {
+        self.description.as_deref().ok_or_else(|| {
+            Error::BlockHandler(
+                block.to_string(),
+                "requires package.description".to_string(),
+            )
+        })
+    }
+
+    /// Returns the repository, or a `block` error saying `what` needs `package.repository`
+    /// (or why the configured one is invalid).
+    pub fn require_repository(&self, block: &str, what: &str) -> Result<&Repository> {
+        self.repository.as_ref().ok_or_else(|| {
+            let message = match &self.repository_error {
+                Some(e) => format!("{what} requires a valid package.repository: {e}"),
+                None => format!("{what} requires package.repository"),
+            };
+            Error::BlockHandler(block.to_string(), message)
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 struct CargoToml {
     package: Package,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, RawDependency>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, RawDependency>,
+    #[serde(default)]
+    build_dependencies: BTreeMap<String, RawDependency>,
+    lib: Option<Target>,
+    #[serde(default, rename = "bin")]
+    bins: Vec<Target>,
+    #[serde(default, rename = "example")]
+    examples: Vec<Target>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 struct Package {
     name: String,
-    description: String,
-    repository: String,
+    version: Option<Inheritable<String>>,
+    edition: Option<Inheritable<String>>,
+    rust_version: Option<Inheritable<String>>,
+    description: Option<Inheritable<String>>,
+    repository: Option<Inheritable<String>>,
+    homepage: Option<Inheritable<String>>,
+    documentation: Option<Inheritable<String>>,
+    license: Option<Inheritable<String>>,
+    license_file: Option<Inheritable<String>>,
+    readme: Option<Inheritable<Readme>>,
+    authors: Option<Inheritable<Vec<String>>>,
+    keywords: Option<Inheritable<Vec<String>>>,
+    categories: Option<Inheritable<Vec<String>>>,
+    metadata: Option<toml::Table>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawDependency {
+    Version(String),
+    Detailed(Dependency),
 }
 
-fn parse_repository_url(repository: &str) -> Result<(String, String)> {
-    trace!("parsing repository url: {:?}", repository);
-    let url = Url::parse(repository).map_err(|e| Error::InvalidRepoUrl(e.to_string()))?;
-    let path = url.path();
-    let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
-    if parts.len() < 2 {
-        return Err(Error::InvalidRepoUrl(format!(
-            "expected user/repo, got: {}",
-            repository
-        )));
+impl From<RawDependency> for Dependency {
+    fn from(raw: RawDependency) -> Self {
+        match raw {
+            RawDependency::Version(version) => Dependency {
+                version: Some(version),
+                ..Default::default()
+            },
+            RawDependency::Detailed(dep) => dep,
+        }
     }
-    let username = parts[0];
-    let repo = parts[1].strip_suffix(".git").unwrap_or(parts[1]);
-    trace!("username: {:?}", username);
-    trace!("repo: {:?}", repo);
-    Ok((username.to_string(), repo.to_string()))
+}
+
+fn dependencies(raw: BTreeMap<String, RawDependency>) -> BTreeMap<String, Dependency> {
+    raw.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+/// Resolves an optional, possibly inherited package field.
+fn resolve<T: Clone>(
+    field: Option<Inheritable<T>>,
+    key: &str,
+    inherited: &mut InheritedPackage,
+    from_workspace: fn(&WorkspacePackage) -> Option<&T>,
+) -> Result<Option<T>> {
+    field
+        .map(|f| f.resolve(key, inherited, from_workspace))
+        .transpose()
 }
 
 pub fn parse(manifest_dir: &Path) -> Result<ParsedManifest> {
@@ -51,19 +200,66 @@ pub fn parse(manifest_dir: &Path) -> Result<ParsedManifest> {
     let path = find_cargo_toml::find(manifest_dir, None::<std::path::PathBuf>, None)
         .next()
         .ok_or(Error::CargoTomlNotFound)?;
-    let content = std::fs::read_to_string(path)?;
+    let content = std::fs::read_to_string(&path)?;
     let toml: Formatted<CargoToml> = parse_toml(&content, Some(FormatOptions::default()))
         .map_err(|e| Error::CargoParse(e.to_string()))?;
-    let name = toml.value.package.name;
-    let description = toml.value.package.description;
-    trace!("name: {:?}", name);
-    trace!("repository: {:?}", toml.value.package.repository);
-    let (username, repository_name) = parse_repository_url(&toml.value.package.repository)?;
+    let toml = toml.value;
+    let package = toml.package;
+    let mut ws = InheritedPackage::new(path.parent().unwrap_or(manifest_dir));
+    trace!("name: {:?}", package.name);
+    let repository = resolve(package.repository, "repository", &mut ws, |w| {
+        w.repository.as_ref()
+    })?;
+    trace!("repository: {:?}", repository);
+    let (repository, repository_error) = match repository.as_deref().map(Repository::parse) {
+        Some(Ok(repository)) => (Some(repository), None),
+        Some(Err(e)) => {
+            trace!("invalid repository: {}", e);
+            (None, Some(e.to_string()))
+        }
+        None => (None, None),
+    };
     Ok(ParsedManifest {
-        name,
-        description,
-        username,
-        repository_name,
+        name: package.name,
+        version: resolve(package.version, "version", &mut ws, |w| w.version.as_ref())?,
+        edition: resolve(package.edition, "edition", &mut ws, |w| w.edition.as_ref())?,
+        rust_version: resolve(package.rust_version, "rust-version", &mut ws, |w| {
+            w.rust_version.as_ref()
+        })?,
+        description: resolve(package.description, "description", &mut ws, |w| {
+            w.description.as_ref()
+        })?,
+        repository,
+        repository_error,
+        homepage: resolve(package.homepage, "homepage", &mut ws, |w| {
+            w.homepage.as_ref()
+        })?,
+        documentation: resolve(package.documentation, "documentation", &mut ws, |w| {
+            w.documentation.as_ref()
+        })?,
+        license: resolve(package.license, "license", &mut ws, |w| w.license.as_ref())?,
+        license_file: resolve(package.license_file, "license-file", &mut ws, |w| {
+            w.license_file.as_ref()
+        })?,
+        readme: resolve(package.readme, "readme", &mut ws, |w| w.readme.as_ref())?,
+        authors: resolve(package.authors, "authors", &mut ws, |w| w.authors.as_ref())?
+            .unwrap_or_default(),
+        keywords: resolve(package.keywords, "keywords", &mut ws, |w| {
+            w.keywords.as_ref()
+        })?
+        .unwrap_or_default(),
+        categories: resolve(package.categories, "categories", &mut ws, |w| {
+            w.categories.as_ref()
+        })?
+        .unwrap_or_default(),
+        features: toml.features,
+        dependencies: dependencies(toml.dependencies),
+        dev_dependencies: dependencies(toml.dev_dependencies),
+        build_dependencies: dependencies(toml.build_dependencies),
+        lib: toml.lib,
+        bins: toml.bins,
+        examples: toml.examples,
+        metadata: package.metadata.unwrap_or_default(),
     })
 }
 
@@ -71,23 +267,6 @@ pub fn parse(manifest_dir: &Path) -> Result<ParsedManifest> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_repository_url() {
-        let (user, repo) =
-            parse_repository_url("https://github.com/betterhyq/automd-rs.git").unwrap();
-        assert_eq!(user, "betterhyq");
-        assert_eq!(repo, "automd-rs");
-        let (user, repo) = parse_repository_url("https://github.com/foo/bar").unwrap();
-        assert_eq!(user, "foo");
-        assert_eq!(repo, "bar");
-    }
-
-    #[test]
-    fn test_parse_repository_url_invalid() {
-        assert!(parse_repository_url("not-a-url").is_err());
-        assert!(parse_repository_url("https://github.com/onlyone").is_err());
-    }
-
     #[test]
     fn test_parse() {
         let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
@@ -95,8 +274,206 @@ mod tests {
         assert!(result.is_ok());
         let m = result.unwrap();
         assert_eq!(m.name, "automd-rs");
-        assert!(!m.username.is_empty());
-        assert!(!m.repository_name.is_empty());
+        let repo = m.repository.unwrap();
+        assert!(!repo.owner.is_empty());
+        assert!(!repo.name.is_empty());
+    }
+
+    #[test]
+    fn test_parse_inherited() {
+        let root = std::env::temp_dir().join("automd_rs_test_inherited");
+        let member = root.join("crates/member");
+        let _ = std::fs::create_dir_all(&member);
+        std::fs::write(
+            root.join("Cargo.toml"),
+            r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "2.0.0"
+description = "shared description"
+repository = "https://github.com/ws/mono.git"
+license-file = "LICENSE"
+keywords = ["shared"]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            member.join("Cargo.toml"),
+            r#"
+[package]
+name = "member"
+version.workspace = true
+description.workspace = true
+repository = { workspace = true }
+license-file.workspace = true
+keywords.workspace = true
+"#,
+        )
+        .unwrap();
+        let m = parse(&member).unwrap();
+        assert_eq!(m.name, "member");
+        assert_eq!(m.version.as_deref(), Some("2.0.0"));
+        assert_eq!(m.description.as_deref(), Some("shared description"));
+        assert_eq!(m.keywords, ["shared"]);
+        assert_eq!(
+            m.license_file.map(std::path::PathBuf::from),
+            Some(root.canonicalize().unwrap().join("LICENSE"))
+        );
+        let repo = m.repository.unwrap();
+        assert_eq!(repo.owner, "ws");
+        assert_eq!(repo.name, "mono");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_inherited_missing() {
+        let root = std::env::temp_dir().join("automd_rs_test_inherited_missing");
+        let member = root.join("member");
+        let _ = std::fs::create_dir_all(&member);
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"member\"\ndescription.workspace = true\nrepository = \"https://github.com/a/b\"\n",
+        )
+        .unwrap();
+        let err = parse(&member).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("[workspace.package] has no description")
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_full_manifest() {
+        let dir = std::env::temp_dir().join("automd_rs_test_full_manifest");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            r#"
+[package]
+name = "full"
+version = "1.2.3"
+edition = "2024"
+rust-version = "1.85"
+license = "MIT OR Apache-2.0"
+authors = ["A <a@example.com>"]
+keywords = ["k"]
+categories = ["development-tools"]
+homepage = "https://example.com"
+documentation = "https://docs.rs/full"
+readme = "docs/README.md"
+
+[package.metadata.automd]
+badges = ["version"]
+
+[lib]
+path = "src/lib.rs"
+
+[[bin]]
+name = "full-cli"
+path = "src/main.rs"
+
+[[example]]
+name = "demo"
+required-features = ["extra"]
+
+[features]
+default = ["extra"]
+extra = ["dep:serde"]
+
+[dependencies]
+log = "0.4"
+serde = { version = "1", optional = true, features = ["derive"] }
+
+[dev-dependencies]
+local = { path = "../local" }
+"#,
+        )
+        .unwrap();
+        let m = parse(&dir).unwrap();
+        assert_eq!(m.version.as_deref(), Some("1.2.3"));
+        assert_eq!(m.edition.as_deref(), Some("2024"));
+        assert_eq!(m.rust_version.as_deref(), Some("1.85"));
+        assert_eq!(m.license.as_deref(), Some("MIT OR Apache-2.0"));
+        assert_eq!(m.authors, ["A <a@example.com>"]);
+        assert_eq!(m.keywords, ["k"]);
+        assert_eq!(m.categories, ["development-tools"]);
+        assert_eq!(m.homepage.as_deref(), Some("https://example.com"));
+        assert_eq!(m.documentation.as_deref(), Some("https://docs.rs/full"));
+        assert_eq!(m.readme, Some(Readme::Path("docs/README.md".to_string())));
+        assert_eq!(
+            m.readme_path(Path::new("crate")),
+            Some(PathBuf::from("crate/docs/README.md"))
+        );
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            Readme::resolve(None, crate_dir),
+            Some(crate_dir.join("README.md"))
+        );
+        assert_eq!(Readme::resolve(None, Path::new("missing")), None);
+        assert_eq!(
+            Readme::resolve(Some(&Readme::Enabled(true)), Path::new("crate")),
+            Some(PathBuf::from("crate/README.md"))
+        );
+        assert_eq!(
+            Readme::resolve(Some(&Readme::Enabled(false)), Path::new("crate")),
+            None
+        );
+        assert_eq!(m.features["default"], ["extra"]);
+        assert_eq!(m.dependencies["log"].version.as_deref(), Some("0.4"));
+        assert!(m.dependencies["serde"].optional);
+        assert_eq!(m.dependencies["serde"].features, ["derive"]);
+        assert_eq!(
+            m.dev_dependencies["local"].path.as_deref(),
+            Some("../local")
+        );
+        assert_eq!(m.lib.unwrap().path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(m.bins[0].name.as_deref(), Some("full-cli"));
+        assert_eq!(m.examples[0].required_features, ["extra"]);
+        assert!(m.metadata["automd"].is_table());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_optional_fields() {
+        let dir = std::env::temp_dir().join("automd_rs_test_optional_fields");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"bare\"\n").unwrap();
+        let m = parse(&dir).unwrap();
+        assert_eq!(m.name, "bare");
+        assert_eq!(m.description, None);
+        assert_eq!(m.repository, None);
+        assert_eq!(
+            m.require_repository("badges", "commit_activity")
+                .unwrap_err()
+                .to_string(),
+            "Block handler 'badges': commit_activity requires package.repository"
+        );
+        assert!(m.require_description("description").is_err());
+
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"bare\"\nrepository = \"not-a-url\"\n",
+        )
+        .unwrap();
+        let m = parse(&dir).unwrap();
+        assert_eq!(m.repository, None);
+        let err = m
+            .require_repository("badges", "commit_activity")
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Block handler 'badges': commit_activity requires a valid package.repository: Invalid repository URL"),
+            "{err}"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
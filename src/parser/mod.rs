@@ -2,4 +2,6 @@
 
 pub mod cargo;
 pub mod readme;
+pub mod repository;
 pub mod tag_options;
+pub mod workspace;
@@ -1,7 +1,12 @@
 //! README block parsing and one-pass replacement for `<!-- automdrs:NAME ... -->` tags.
 
-use crate::error::Result;
+use crate::error::{BlockFailure, Diagnostic, Error, Result};
+use crate::generators::template;
 use crate::handler::{BlockHandler, UpdateContext};
+use crate::parser::tag_options;
+use crate::report::BlockReport;
+use log::{trace, warn};
+use std::time::Instant;
 
 const OPEN_PREFIX: &str = "<!-- automdrs:";
 const OPEN_SUFFIX: &str = "-->";
@@ -12,6 +17,12 @@ const CLOSE_TAG: &str = "<!-- /automdrs -->";
 pub struct BlockRequest {
     pub name: String,
     pub open_tag_line: String,
+    /// 1-based line number of the open tag.
+    pub line: usize,
+    /// 1-based line number of the close tag; `None` if the block is never closed.
+    pub end_line: Option<usize>,
+    /// Current lines between the open and close tags.
+    pub body: Vec<String>,
 }
 
 /// Parses block name from line like `<!-- automdrs:badges version -->` → `badges`.
@@ -22,35 +33,367 @@ pub fn parse_block_name(line: &str) -> Option<&str> {
     }
     t.strip_prefix(OPEN_PREFIX)?
         .strip_suffix(OPEN_SUFFIX)?
-        .trim()
         .split_whitespace()
         .next()
 }
 
+/// What a README line means to the block scanner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineTag<'a> {
+    Open(&'a str),
+    Close,
+    Text,
+}
+
+/// Markdown context that hides tags: fenced code, indented code and multi-line HTML comments.
+#[derive(Debug)]
+struct CodeContext {
+    /// Fence character and length of the open fenced code block.
+    fence: Option<(char, usize)>,
+    in_comment: bool,
+    in_indented: bool,
+    prev_blank: bool,
+}
+
+/// Leading indentation in columns (tabs count as 4).
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Fence character, run length and the rest of the line, if `line` starts a ```` ``` ```` / `~~~` fence.
+fn fence_run(line: &str) -> Option<(char, usize, &str)> {
+    if indent_width(line) > 3 {
+        return None;
+    }
+    let t = line.trim_start();
+    let ch = t.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = t.chars().take_while(|c| *c == ch).count();
+    (len >= 3).then(|| (ch, len, &t[len..]))
+}
+
+impl CodeContext {
+    fn new() -> Self {
+        Self {
+            fence: None,
+            in_comment: false,
+            in_indented: false,
+            prev_blank: true,
+        }
+    }
+
+    /// Advances past `line`; returns true if it is code or comment, where tags are plain text.
+    fn is_code(&mut self, line: &str) -> bool {
+        let blank = line.trim().is_empty();
+        let was_blank = std::mem::replace(&mut self.prev_blank, blank);
+        if let Some((ch, len)) = self.fence {
+            if let Some((c, n, rest)) = fence_run(line)
+                && c == ch
+                && n >= len
+                && rest.trim().is_empty()
+            {
+                self.fence = None;
+            }
+            return true;
+        }
+        if self.in_comment {
+            self.in_comment = !line.contains("-->");
+            return true;
+        }
+        if !blank && indent_width(line) >= 4 && (was_blank || self.in_indented) {
+            self.in_indented = true;
+            return true;
+        }
+        if !blank {
+            self.in_indented = false;
+        }
+        if let Some((ch, len, info)) = fence_run(line)
+            && !(ch == '`' && info.contains('`'))
+        {
+            self.fence = Some((ch, len));
+            return true;
+        }
+        // Like a CommonMark HTML block, a comment only hides lines when it starts the line;
+        // `<!--` in prose or an inline code span is text.
+        if indent_width(line) <= 3
+            && let Some(rest) = line.trim_start().strip_prefix("<!--")
+            && !rest.contains("-->")
+        {
+            self.in_comment = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// One README line as seen by the scanner.
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    /// 1-based line number.
+    number: usize,
+    /// The line without its line ending.
+    text: &'a str,
+    /// The line exactly as in the file, including `\n` / `\r\n` (absent on an unterminated last line).
+    raw: &'a str,
+    tag: LineTag<'a>,
+}
+
+/// Tags every line of `content`. Tags inside fenced or indented code and multi-line
+/// HTML comments are text, so documented examples are left alone. Inside a block only the
+/// close tag counts: the body is generated content (e.g. an included file with an unbalanced
+/// fence or tag examples) and must not hide it.
+fn scan(content: &str) -> impl Iterator<Item = Line<'_>> {
+    let mut code = CodeContext::new();
+    let mut in_block = false;
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(move |(i, raw)| {
+            let text = raw.strip_suffix('\n').unwrap_or(raw);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            let tag = if in_block {
+                if text.trim() == CLOSE_TAG {
+                    in_block = false;
+                    code = CodeContext::new();
+                    LineTag::Close
+                } else {
+                    LineTag::Text
+                }
+            } else if code.is_code(text) {
+                LineTag::Text
+            } else {
+                match parse_block_name(text) {
+                    Some(name) => {
+                        in_block = true;
+                        LineTag::Open(name)
+                    }
+                    None if text.trim() == CLOSE_TAG => LineTag::Close,
+                    None => LineTag::Text,
+                }
+            };
+            Line {
+                number: i + 1,
+                text,
+                raw,
+                tag,
+            }
+        })
+}
+
+/// Line ending used by `content`: `\r\n` if its first line ends with one, else `\n`.
+fn line_ending(content: &str) -> &'static str {
+    match content.find('\n') {
+        Some(i) if content[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
 /// Collects all automdrs block requests in document order.
 pub fn parse_readme_blocks(content: &str) -> Vec<BlockRequest> {
-    let mut requests = Vec::new();
+    let mut requests: Vec<BlockRequest> = Vec::new();
     let mut in_block = false;
-    for line in content.lines() {
-        let t = line.trim();
-        if !in_block {
-            if let Some(name) = parse_block_name(line) {
+    for line in scan(content) {
+        match (in_block, line.tag) {
+            (false, LineTag::Open(name)) => {
                 in_block = true;
                 requests.push(BlockRequest {
                     name: name.to_string(),
-                    open_tag_line: line.to_string(),
+                    open_tag_line: line.text.to_string(),
+                    line: line.number,
+                    end_line: None,
+                    body: Vec::new(),
                 });
             }
-            continue;
+            (false, _) => {}
+            (true, LineTag::Close) => {
+                in_block = false;
+                if let Some(req) = requests.last_mut() {
+                    req.end_line = Some(line.number);
+                }
+            }
+            (true, _) => {
+                if let Some(req) = requests.last_mut() {
+                    req.body.push(line.text.to_string());
+                }
+            }
+        }
+    }
+    requests
+}
+
+/// Collects the current body lines of every block in document order.
+pub fn block_bodies(content: &str) -> Vec<Vec<String>> {
+    parse_readme_blocks(content)
+        .into_iter()
+        .map(|req| req.body)
+        .collect()
+}
+
+/// Finds unclosed blocks, close tags without an open tag, and open tags with malformed
+/// options (e.g. an unterminated quote). An open tag inside a block is part of its body.
+/// Replacing blocks is only safe when this returns no diagnostics.
+pub fn validate_blocks(content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut open: Option<(Line, &str)> = None;
+    for line in scan(content) {
+        if let LineTag::Open(name) = line.tag
+            && let Err(Error::TagOptions { column, message }) =
+                tag_options::try_parse_tag_options(line.text, name)
+        {
+            diagnostics.push(Diagnostic {
+                line: line.number,
+                column,
+                message,
+                snippet: line.text.to_string(),
+            });
         }
-        if t == CLOSE_TAG.trim() {
-            in_block = false;
+        match (line.tag, open) {
+            (LineTag::Open(name), _) => open = Some((line, name)),
+            (LineTag::Close, None) => diagnostics.push(Diagnostic::new(
+                line.number,
+                line.text,
+                "close tag without a matching open tag".to_string(),
+            )),
+            (LineTag::Close, Some(_)) => open = None,
+            (LineTag::Text, _) => {}
         }
     }
+    if let Some((line, name)) = open {
+        diagnostics.push(Diagnostic::new(
+            line.number,
+            line.text,
+            format!("block '{name}' is never closed (expected `{CLOSE_TAG}`)"),
+        ));
+    }
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
+/// [`Error::MalformedBlocks`] if [`validate_blocks`] finds any problem.
+pub fn ensure_well_formed(content: &str) -> Result<()> {
+    let diagnostics = validate_blocks(content);
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
+    trace!("diagnostics: {:?}", diagnostics);
+    Err(Error::MalformedBlocks(diagnostics))
+}
+
+/// Returns the requests whose current body differs from the `generated` lines.
+pub fn changed_blocks<'a>(
+    content: &str,
+    requests: &'a [BlockRequest],
+    generated: &[Vec<String>],
+) -> Vec<&'a BlockRequest> {
+    let bodies = block_bodies(content);
     requests
+        .iter()
+        .zip(generated)
+        .enumerate()
+        .filter(|(i, (_, lines))| bodies.get(*i).map(|b| b.join("\n")) != Some(lines.join("\n")))
+        .map(|(_, (req, _))| req)
+        .collect()
+}
+
+/// Generates one block. Blocks disabled in the config keep their body; blocks with a
+/// configured template render it instead of the handler.
+fn generate_block(
+    req: &BlockRequest,
+    handler: &dyn BlockHandler,
+    context: &UpdateContext,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>> {
+    if context.automd.is_disabled(&req.name) {
+        trace!("block '{}' is disabled, kept as is", req.name);
+        return Ok(req.body.clone());
+    }
+    if let Some(tpl) = context.automd.templates.get(&req.name) {
+        let opts = context.automd.options(&req.name, &req.open_tag_line);
+        return Ok(template::generate(tpl, &context.config, &opts));
+    }
+    let start = warnings.len();
+    let lines = handler.generate_with_warnings(&req.name, &req.open_tag_line, context, warnings);
+    warnings[start..].iter().for_each(|w| warn!("{}", w));
+    lines
 }
 
-/// Runs handler per request and returns generated lines in order.
+/// Generates one block and describes it. On error the report keeps the current body and
+/// the error is returned alongside.
+fn report_block(
+    req: &BlockRequest,
+    handler: &dyn BlockHandler,
+    context: &UpdateContext,
+) -> (BlockReport, Option<Error>) {
+    let started = Instant::now();
+    let mut warnings = Vec::new();
+    let result = generate_block(req, handler, context, &mut warnings);
+    let duration = started.elapsed();
+    trace!("block '{}' generated in {:?}", req.name, duration);
+    let (new_body, error) = match result {
+        // Entries may span lines; each is followed by a line break when written.
+        Ok(entries) => (
+            entries
+                .iter()
+                .flat_map(|e| e.split('\n'))
+                .map(|l| l.strip_suffix('\r').unwrap_or(l).to_string())
+                .collect(),
+            None,
+        ),
+        Err(e) => (req.body.clone(), Some(e)),
+    };
+    let report = BlockReport {
+        name: req.name.clone(),
+        options: context
+            .automd
+            .options(&req.name, &req.open_tag_line)
+            .into_iter()
+            .collect(),
+        start_line: req.line,
+        end_line: req.end_line,
+        changed: new_body != req.body,
+        old_body: req.body.clone(),
+        new_body,
+        duration,
+        warnings,
+        error: error.as_ref().map(Error::to_string),
+    };
+    (report, error)
+}
+
+fn failure(req: &BlockRequest, source: Error) -> BlockFailure {
+    BlockFailure {
+        name: req.name.clone(),
+        line: req.line,
+        source,
+    }
+}
+
+/// Generates every block, recording options, timing and warnings per block. A failing
+/// block stops the run, or with `keep_going` keeps its current body and is collected.
+pub fn generate_blocks(
+    requests: &[BlockRequest],
+    handler: &dyn BlockHandler,
+    context: &UpdateContext,
+    keep_going: bool,
+) -> Result<(Vec<BlockReport>, Vec<BlockFailure>)> {
+    let mut reports = Vec::with_capacity(requests.len());
+    let mut failures = Vec::new();
+    for req in requests {
+        let (report, error) = report_block(req, handler, context);
+        if let Some(source) = error {
+            if !keep_going {
+                return Err(source);
+            }
+            failures.push(failure(req, source));
+        }
+        reports.push(report);
+    }
+    Ok((reports, failures))
+}
+
+/// Runs handler per request and returns generated lines in order; stops at the first error.
 pub fn assign_and_generate(
     requests: &[BlockRequest],
     handler: &dyn BlockHandler,
@@ -58,46 +401,74 @@ pub fn assign_and_generate(
 ) -> Result<Vec<Vec<String>>> {
     let mut out = Vec::with_capacity(requests.len());
     for req in requests {
-        out.push(handler.generate(&req.name, &req.open_tag_line, context)?);
+        out.push(generate_block(req, handler, context, &mut Vec::new())?);
     }
     Ok(out)
 }
 
+/// Like [`assign_and_generate`], but a failing block keeps its current body and its error
+/// is collected, so every other block is still generated.
+pub fn assign_and_generate_keep_going(
+    requests: &[BlockRequest],
+    handler: &dyn BlockHandler,
+    context: &UpdateContext,
+) -> (Vec<Vec<String>>, Vec<BlockFailure>) {
+    let mut out = Vec::with_capacity(requests.len());
+    let mut failures = Vec::new();
+    for req in requests {
+        match generate_block(req, handler, context, &mut Vec::new()) {
+            Ok(lines) => out.push(lines),
+            Err(source) => {
+                out.push(req.body.clone());
+                failures.push(failure(req, source));
+            }
+        }
+    }
+    (out, failures)
+}
+
+/// Appends one generated entry and a line ending, converting newlines inside it to `eol`.
+fn push_generated(out: &mut String, s: &str, eol: &str) {
+    if eol == "\n" {
+        out.push_str(s);
+    } else {
+        out.push_str(&s.replace("\r\n", "\n").replace('\n', eol));
+    }
+    out.push_str(eol);
+}
+
 /// Replaces block bodies with `generated` in one pass. Output order matches block order.
+/// Everything outside block bodies is copied byte for byte; generated lines use the
+/// file's line ending (see [`line_ending`]).
 pub fn replace_blocks_once(content: &str, generated: &[Vec<String>]) -> String {
     let cap = content.len().saturating_add(512);
     let mut out = String::with_capacity(cap);
+    let eol = line_ending(content);
     let mut in_block = false;
     let mut idx = 0usize;
-    for line in content.lines() {
-        let t = line.trim();
-        if !in_block {
-            if parse_block_name(line).is_some() {
+    for line in scan(content) {
+        match (in_block, line.tag) {
+            (false, LineTag::Open(_)) => {
                 in_block = true;
-                out.push_str(line);
-                out.push('\n');
+                out.push_str(line.raw);
+                if !line.raw.ends_with('\n') {
+                    out.push_str(eol);
+                }
                 if idx < generated.len() {
                     for s in &generated[idx] {
-                        out.push_str(s);
-                        out.push('\n');
+                        push_generated(&mut out, s, eol);
                     }
                 }
                 idx += 1;
-                continue;
             }
-            out.push_str(line);
-            out.push('\n');
-            continue;
-        }
-        if t == CLOSE_TAG.trim() {
-            in_block = false;
-            out.push_str(line);
-            out.push('\n');
+            (false, _) => out.push_str(line.raw),
+            (true, LineTag::Close) => {
+                in_block = false;
+                out.push_str(line.raw);
+            }
+            (true, _) => {}
         }
     }
-    if out.ends_with('\n') {
-        out.pop();
-    }
     out
 }
 
@@ -106,6 +477,7 @@ pub fn update_readme(
     handler: &dyn BlockHandler,
     context: &UpdateContext,
 ) -> Result<String> {
+    ensure_well_formed(content)?;
     let requests = parse_readme_blocks(content);
     let generated = assign_and_generate(&requests, handler, context)?;
     Ok(replace_blocks_once(content, &generated))
@@ -137,6 +509,180 @@ mod tests {
         assert_eq!(reqs.len(), 2);
         assert_eq!(reqs[0].name, "badges");
         assert_eq!(reqs[1].name, "contributors");
+        assert_eq!(reqs[0].line, 2);
+        assert_eq!(reqs[1].line, 5);
+        assert_eq!(reqs[0].end_line, Some(3));
+        assert_eq!(reqs[1].end_line, Some(6));
+    }
+
+    #[test]
+    fn test_tags_in_code_are_ignored() {
+        let content = "\
+Intro
+```markdown
+<!-- automdrs:badges version -->
+<!-- /automdrs -->
+```
+~~~~
+<!-- automdrs:file -->
+~~~
+<!-- /automdrs -->
+~~~~
+
+    <!-- automdrs:cargo-add -->
+    <!-- /automdrs -->
+
+<!--
+<!-- automdrs:contributors -->
+-->
+<!-- automdrs:with-automdrs -->
+old
+<!-- /automdrs -->
+Tail";
+        let reqs = parse_readme_blocks(content);
+        assert_eq!(reqs.len(), 1);
+        assert_eq!(reqs[0].name, "with-automdrs");
+        assert_eq!(reqs[0].line, 18);
+        assert!(validate_blocks(content).is_empty());
+
+        let out = replace_blocks_once(content, &[vec!["new".to_string()]]);
+        assert_eq!(out, content.replace("\nold\n", "\nnew\n"));
+    }
+
+    #[test]
+    fn test_comment_opener_in_prose() {
+        let content = "\
+Comments start with `<!--` in HTML.
+<!-- automdrs:cargo-add -->
+<!-- /automdrs -->
+Text <!-- note
+-->
+<!-- automdrs:badges -->
+<!-- /automdrs -->
+";
+        let reqs = parse_readme_blocks(content);
+        let found: Vec<(&str, usize)> = reqs.iter().map(|r| (r.name.as_str(), r.line)).collect();
+        assert_eq!(found, [("cargo-add", 2), ("badges", 6)]);
+        assert!(validate_blocks(content).is_empty());
+    }
+
+    #[test]
+    fn test_block_body_cannot_hide_close_tag() {
+        let dir = std::env::temp_dir().join("automd_rs_test_block_body");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("snippet.md"),
+            "```\n<!--\n<!-- automdrs:badges -->\ntext\n",
+        )
+        .unwrap();
+        let ctx = crate::handler::UpdateContext::new(
+            crate::parser::cargo::ParsedManifest {
+                name: "x".to_string(),
+                ..Default::default()
+            },
+            dir.clone(),
+        );
+        let content = "<!-- automdrs:file src=\"snippet.md\" -->\n<!-- /automdrs -->\n\n<!-- automdrs:cargo-add -->\n<!-- /automdrs -->\n";
+        let once = update_readme(content, &crate::handler::DefaultHandler, &ctx).unwrap();
+        assert!(once.contains("\n<!--\n<!-- automdrs:badges -->\ntext\n"));
+        assert!(once.contains("cargo add x"));
+        assert!(validate_blocks(&once).is_empty());
+        let names: Vec<String> = parse_readme_blocks(&once)
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["file", "cargo-add"]);
+
+        let twice = update_readme(&once, &crate::handler::DefaultHandler, &ctx).unwrap();
+        assert_eq!(twice, once);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_blocks() {
+        let ok = "<!-- automdrs:badges -->\nx\n<!-- /automdrs -->\n";
+        assert!(validate_blocks(ok).is_empty());
+        assert!(ensure_well_formed(ok).is_ok());
+
+        let content = "<!-- /automdrs -->\n<!-- automdrs:badges -->\n  <!-- automdrs:file -->\n<!-- /automdrs -->\n\n<!-- automdrs:cargo-add -->\nrest";
+        let diagnostics = validate_blocks(content);
+        let found: Vec<(usize, usize, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.column, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, 1, "close tag without a matching open tag"),
+                (
+                    6,
+                    1,
+                    "block 'cargo-add' is never closed (expected `<!-- /automdrs -->`)"
+                ),
+            ]
+        );
+        assert_eq!(
+            parse_readme_blocks(content)[0].body,
+            ["  <!-- automdrs:file -->"]
+        );
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "6:1: block 'cargo-add' is never closed (expected `<!-- /automdrs -->`)\n    6 | <!-- automdrs:cargo-add -->"
+        );
+        let ctx = crate::handler::UpdateContext::new(
+            crate::parser::cargo::ParsedManifest::default(),
+            std::path::PathBuf::from("."),
+        );
+        let err = update_readme(content, &crate::handler::DefaultHandler, &ctx).unwrap_err();
+        assert!(matches!(err, Error::MalformedBlocks(d) if d.len() == 2));
+
+        let content = "<!-- automdrs:contributors author=\"Yong Qi -->\n<!-- /automdrs -->\n";
+        let diagnostics = validate_blocks(content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 35));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("unterminated quote in value of 'author'")
+        );
+    }
+
+    #[test]
+    fn test_update_readme_disabled_and_template() {
+        let content = "<!-- automdrs:with-automdrs -->\nmine\n<!-- /automdrs -->\n<!-- automdrs:cargo-install tool=binstall -->\n<!-- /automdrs -->";
+        let mut ctx = crate::handler::UpdateContext::new(
+            crate::parser::cargo::ParsedManifest {
+                name: "n".to_string(),
+                ..Default::default()
+            },
+            std::path::PathBuf::from("."),
+        );
+        ctx.automd.disabled.insert("with-automdrs".to_string());
+        ctx.automd.templates.insert(
+            "cargo-install".to_string(),
+            "cargo {{tool}} {{name}}".to_string(),
+        );
+        let out = update_readme(content, &crate::handler::DefaultHandler, &ctx).unwrap();
+        assert_eq!(
+            out,
+            "<!-- automdrs:with-automdrs -->\nmine\n<!-- /automdrs -->\n<!-- automdrs:cargo-install tool=binstall -->\ncargo binstall n\n<!-- /automdrs -->"
+        );
+    }
+
+    #[test]
+    fn test_changed_blocks() {
+        let content = "A\n<!-- automdrs:badges -->\nold\n<!-- /automdrs -->\n<!-- automdrs:file -->\nsame\n<!-- /automdrs -->\n";
+        let reqs = parse_readme_blocks(content);
+        assert_eq!(
+            block_bodies(content),
+            vec![vec!["old".to_string()], vec!["same".to_string()]]
+        );
+        let generated = vec![vec!["new".to_string()], vec!["same".to_string()]];
+        let changed = changed_blocks(content, &reqs, &generated);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].name, "badges");
+        assert_eq!(changed[0].line, 2);
     }
 
     #[test]
@@ -151,24 +697,53 @@ mod tests {
         assert!(out.contains("Rest"));
     }
 
+    #[test]
+    fn test_replace_blocks_once_line_endings() {
+        let generated = vec![vec!["a".to_string(), "\nb\nc\n".to_string()]];
+        let content = "T\r\n<!-- automdrs:x -->\r\nold\r\n<!-- /automdrs -->\r\nEnd\r\n";
+        assert_eq!(
+            replace_blocks_once(content, &generated),
+            "T\r\n<!-- automdrs:x -->\r\na\r\n\r\nb\r\nc\r\n\r\n<!-- /automdrs -->\r\nEnd\r\n"
+        );
+        let content = "T\n<!-- automdrs:x -->\n<!-- /automdrs -->\nEnd\n";
+        assert_eq!(
+            replace_blocks_once(content, &generated),
+            "T\n<!-- automdrs:x -->\na\n\nb\nc\n\n<!-- /automdrs -->\nEnd\n"
+        );
+        let content = "T\n<!-- automdrs:x -->\n<!-- /automdrs -->";
+        assert_eq!(
+            replace_blocks_once(content, &[vec![]]),
+            "T\n<!-- automdrs:x -->\n<!-- /automdrs -->"
+        );
+        let content = "T\n<!-- automdrs:x -->\nsame\n<!-- /automdrs -->\n";
+        assert_eq!(
+            replace_blocks_once(content, &[vec!["same".to_string()]]),
+            content
+        );
+    }
+
     #[test]
     fn test_assign_and_generate() {
         let requests = vec![
             BlockRequest {
                 name: "badges".to_string(),
                 open_tag_line: "<!-- automdrs:badges version -->".to_string(),
+                line: 1,
+                end_line: Some(2),
+                body: vec![],
             },
             BlockRequest {
                 name: "unknown".to_string(),
                 open_tag_line: "<!-- automdrs:unknown -->".to_string(),
+                line: 3,
+                end_line: Some(4),
+                body: vec![],
             },
         ];
         let ctx = crate::handler::UpdateContext::new(
             crate::parser::cargo::ParsedManifest {
                 name: "x".to_string(),
-                description: "d".to_string(),
-                username: "u".to_string(),
-                repository_name: "r".to_string(),
+                ..Default::default()
             },
             std::path::PathBuf::from("."),
         );
@@ -179,15 +754,37 @@ mod tests {
         assert!(out[1].is_empty());
     }
 
+    #[test]
+    fn test_assign_and_generate_keep_going() {
+        let content = "<!-- automdrs:file src=\"missing.md\" -->\nold\n<!-- /automdrs -->\n<!-- automdrs:with-automdrs -->\n<!-- /automdrs -->\n<!-- automdrs:description -->\n<!-- /automdrs -->";
+        let requests = parse_readme_blocks(content);
+        let ctx = crate::handler::UpdateContext::new(
+            crate::parser::cargo::ParsedManifest {
+                name: "x".to_string(),
+                ..Default::default()
+            },
+            std::env::temp_dir(),
+        );
+        let handler = crate::handler::DefaultHandler;
+        assert!(assign_and_generate(&requests, &handler, &ctx).is_err());
+
+        let (out, failures) = assign_and_generate_keep_going(&requests, &handler, &ctx);
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[0], ["old"]);
+        assert!(out[1][0].contains("automd-rs"));
+        assert!(out[2].is_empty());
+        let failed: Vec<(&str, usize)> =
+            failures.iter().map(|f| (f.name.as_str(), f.line)).collect();
+        assert_eq!(failed, [("file", 1), ("description", 6)]);
+    }
+
     #[test]
     fn test_update_readme() {
         let content = "P\n<!-- automdrs:with-automdrs -->\n<!-- /automdrs -->\nQ";
         let ctx = crate::handler::UpdateContext::new(
             crate::parser::cargo::ParsedManifest {
                 name: "n".to_string(),
-                description: "d".to_string(),
-                username: "u".to_string(),
-                repository_name: "r".to_string(),
+                ..Default::default()
             },
             std::path::PathBuf::from("."),
         );
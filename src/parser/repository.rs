@@ -0,0 +1,321 @@
+//! Repository URL parsing: `package.repository` → host kind, owner path, repo name, web URL.
+
+use crate::error::{Error, Result};
+use log::trace;
+use url::Url;
+
+/// Code hosting service, detected from the repository's domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HostKind {
+    #[default]
+    GitHub,
+    GitLab,
+    Codeberg,
+    /// Self-hosted Gitea / Forgejo (domain contains `gitea`).
+    Gitea,
+    /// Unknown host: URLs are still parsed, host-specific badges are skipped.
+    Other,
+}
+
+impl HostKind {
+    fn from_domain(domain: &str) -> Self {
+        let domain = domain.strip_prefix("www.").unwrap_or(domain);
+        match domain {
+            "github.com" => HostKind::GitHub,
+            "gitlab.com" => HostKind::GitLab,
+            "codeberg.org" => HostKind::Codeberg,
+            d if d.starts_with("gitlab.") => HostKind::GitLab,
+            d if d.contains("gitea") => HostKind::Gitea,
+            _ => HostKind::Other,
+        }
+    }
+
+    /// Parses a host name as written in config (`github`, `gitlab`, `codeberg`, `gitea`,
+    /// `forgejo`, `other`), ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "github" => Some(HostKind::GitHub),
+            "gitlab" => Some(HostKind::GitLab),
+            "codeberg" => Some(HostKind::Codeberg),
+            "gitea" | "forgejo" => Some(HostKind::Gitea),
+            "other" => Some(HostKind::Other),
+            _ => None,
+        }
+    }
+
+    /// Whether the host allows nested groups (`group/subgroup/repo`).
+    fn nested_owners(self) -> bool {
+        matches!(self, HostKind::GitLab | HostKind::Other)
+    }
+
+    /// Display name, e.g. for badge alt text.
+    pub fn label(self) -> &'static str {
+        match self {
+            HostKind::GitHub => "GitHub",
+            HostKind::GitLab => "GitLab",
+            HostKind::Codeberg => "Codeberg",
+            HostKind::Gitea => "Gitea",
+            HostKind::Other => "Git",
+        }
+    }
+}
+
+/// Splits a repository URL into `(domain, web port, path)`; scp-style `[user@]host:path` has
+/// no `://`. Only an explicit port of an `http(s)` URL is kept: an ssh port is not the web port.
+fn split_host_path(repository: &str) -> Result<(String, Option<u16>, String)> {
+    if !repository.contains("://")
+        && let Some((user_host, path)) = repository.split_once(':')
+        && !user_host.contains('/')
+    {
+        let domain = user_host.rsplit('@').next().unwrap_or(user_host);
+        if !domain.is_empty() {
+            return Ok((domain.to_string(), None, path.to_string()));
+        }
+    }
+    let url = Url::parse(repository).map_err(|e| Error::InvalidRepoUrl(e.to_string()))?;
+    let domain = url
+        .host_str()
+        .ok_or_else(|| Error::InvalidRepoUrl(format!("missing host: {}", repository)))?;
+    let port = match url.scheme() {
+        "http" | "https" => url.port(),
+        _ => None,
+    };
+    Ok((domain.to_string(), port, url.path().to_string()))
+}
+
+/// Owner segments followed by the repo name, with `.git` and page suffixes removed.
+fn repo_path_segments(path: &str, host: HostKind) -> Vec<String> {
+    let mut parts = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        // GitLab pages live under `/-/`; `/tree/...`, `/blob/...` and Gitea's `/src/...` are
+        // branch/file views, on any host since unknown hosts allow nested owners.
+        if segment == "-" || (parts.len() >= 2 && matches!(segment, "tree" | "blob" | "src")) {
+            break;
+        }
+        if let Some(name) = segment.strip_suffix(".git") {
+            parts.push(name.to_string());
+            break;
+        }
+        parts.push(segment.to_string());
+    }
+    if !host.nested_owners() {
+        parts.truncate(2);
+    }
+    parts
+}
+
+/// A repository on a code host.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Repository {
+    pub host: HostKind,
+    /// Host name, e.g. `github.com` or `git.example.com`.
+    pub domain: String,
+    /// Non-default port of the web UI, e.g. `3000` for `https://git.example.com:3000/me/repo`.
+    pub port: Option<u16>,
+    /// User or organisation; nested groups joined with `/` (e.g. `group/subgroup`).
+    pub owner: String,
+    pub name: String,
+    /// Normalized https URL of the repository page.
+    pub web_url: String,
+}
+
+impl Repository {
+    /// Parses a repository URL into owner/name and an https web URL. Accepts
+    /// `https://` / `http://` / `ssh://` / `git://` URLs and scp-style `git@host:owner/repo.git`,
+    /// ignoring trailing slashes and anything after the repo (`.git/...`, `/tree/main`, `/src/branch/main`, `/-/...`).
+    pub fn parse(repository: &str) -> Result<Self> {
+        trace!("parsing repository url: {:?}", repository);
+        let (domain, port, path) = split_host_path(repository.trim())?;
+        let host = HostKind::from_domain(&domain);
+        let parts = repo_path_segments(&path, host);
+        if parts.len() < 2 {
+            return Err(Error::InvalidRepoUrl(format!(
+                "expected user/repo, got: {}",
+                repository
+            )));
+        }
+        let mut repo = Self {
+            host,
+            domain,
+            port,
+            ..Default::default()
+        };
+        repo.set_path(parts);
+        trace!(
+            "host: {:?}, owner: {:?}, name: {:?}",
+            host, repo.owner, repo.name
+        );
+        Ok(repo)
+    }
+
+    /// Treats the repository as hosted on `host`, e.g. a self-hosted Gitea on a domain that
+    /// is not recognised; owner and name are re-split for hosts without nested groups.
+    pub fn set_host(&mut self, host: HostKind) {
+        self.host = host;
+        let mut parts: Vec<String> = self.owner.split('/').map(str::to_string).collect();
+        parts.push(std::mem::take(&mut self.name));
+        if !host.nested_owners() {
+            parts.truncate(2);
+        }
+        self.set_path(parts);
+    }
+
+    /// Sets owner segments and repo name (the last of `parts`) and the web URL.
+    fn set_path(&mut self, mut parts: Vec<String>) {
+        self.name = parts.pop().unwrap_or_default();
+        self.owner = parts.join("/");
+        self.web_url = format!("{}/{}/{}", self.host_url(), self.owner, self.name);
+    }
+
+    /// `owner/name`, e.g. `betterhyq/automd-rs`.
+    pub fn slug(&self) -> String {
+        format!("{}/{}", self.owner, self.name)
+    }
+
+    /// Base URL of the host, e.g. `https://codeberg.org` or `https://git.example.com:3000`.
+    pub fn host_url(&self) -> String {
+        match self.port {
+            Some(port) => format!("https://{}:{}", self.domain, port),
+            None => format!("https://{}", self.domain),
+        }
+    }
+
+    /// Profile page of the top-level owner (user or root group).
+    pub fn owner_url(&self) -> String {
+        let top = self.owner.split('/').next().unwrap_or_default();
+        format!("{}/{}", self.host_url(), top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github() {
+        let repo = Repository::parse("https://github.com/betterhyq/automd-rs.git").unwrap();
+        assert_eq!(repo.host, HostKind::GitHub);
+        assert_eq!(repo.owner, "betterhyq");
+        assert_eq!(repo.name, "automd-rs");
+        assert_eq!(repo.web_url, "https://github.com/betterhyq/automd-rs");
+        assert_eq!(repo.owner_url(), "https://github.com/betterhyq");
+        let repo = Repository::parse("https://github.com/foo/bar").unwrap();
+        assert_eq!(repo.slug(), "foo/bar");
+    }
+
+    #[test]
+    fn test_parse_gitlab_nested_groups() {
+        let repo = Repository::parse("https://gitlab.com/group/sub/project.git").unwrap();
+        assert_eq!(repo.host, HostKind::GitLab);
+        assert_eq!(repo.owner, "group/sub");
+        assert_eq!(repo.name, "project");
+        assert_eq!(repo.web_url, "https://gitlab.com/group/sub/project");
+        assert_eq!(repo.owner_url(), "https://gitlab.com/group");
+
+        let repo = Repository::parse("https://gitlab.example.com/a/b").unwrap();
+        assert_eq!(repo.host, HostKind::GitLab);
+        assert_eq!(repo.domain, "gitlab.example.com");
+    }
+
+    #[test]
+    fn test_parse_codeberg_and_gitea() {
+        let repo = Repository::parse("https://codeberg.org/forgejo/forgejo").unwrap();
+        assert_eq!(repo.host, HostKind::Codeberg);
+        assert_eq!(repo.slug(), "forgejo/forgejo");
+
+        let repo = Repository::parse("https://gitea.example.org/me/tool").unwrap();
+        assert_eq!(repo.host, HostKind::Gitea);
+        assert_eq!(repo.host_url(), "https://gitea.example.org");
+
+        let repo = Repository::parse("https://git.example.org/me/tool").unwrap();
+        assert_eq!(repo.host, HostKind::Other);
+    }
+
+    #[test]
+    fn test_parse_ssh_and_scp() {
+        for url in [
+            "git@github.com:foo/bar.git",
+            "github.com:foo/bar",
+            "ssh://git@github.com/foo/bar",
+            "ssh://git@github.com:22/foo/bar.git",
+            "git://github.com/foo/bar.git",
+            "git+ssh://git@github.com/foo/bar.git",
+        ] {
+            let repo = Repository::parse(url).unwrap();
+            assert_eq!(repo.host, HostKind::GitHub, "{url}");
+            assert_eq!(repo.slug(), "foo/bar", "{url}");
+            assert_eq!(repo.web_url, "https://github.com/foo/bar", "{url}");
+        }
+        let repo = Repository::parse("git@gitlab.com:group/sub/project.git").unwrap();
+        assert_eq!(repo.owner, "group/sub");
+        assert_eq!(repo.name, "project");
+    }
+
+    #[test]
+    fn test_parse_suffixes() {
+        for url in [
+            "https://github.com/foo/bar/",
+            "https://github.com/foo/bar/tree/main",
+            "https://github.com/foo/bar.git/tree/main",
+            "http://github.com/foo/bar/blob/main/README.md",
+            "https://codeberg.org/foo/bar/src/branch/main",
+            "https://git.example.org/foo/bar/src/branch/main",
+            "https://git.example.org/foo/bar/tree/main",
+        ] {
+            let repo = Repository::parse(url).unwrap();
+            assert_eq!(repo.slug(), "foo/bar", "{url}");
+            assert!(repo.web_url.starts_with("https://"), "{url}");
+        }
+        for url in [
+            "https://gitlab.com/group/sub/project/-/tree/main",
+            "https://gitlab.com/group/sub/project/tree/main",
+            "https://gitlab.com/group/sub/project.git/",
+        ] {
+            let repo = Repository::parse(url).unwrap();
+            assert_eq!(repo.owner, "group/sub", "{url}");
+            assert_eq!(repo.name, "project", "{url}");
+        }
+    }
+
+    #[test]
+    fn test_parse_port() {
+        let repo = Repository::parse("https://git.example.com:3000/me/tool.git").unwrap();
+        assert_eq!(repo.port, Some(3000));
+        assert_eq!(repo.host_url(), "https://git.example.com:3000");
+        assert_eq!(repo.web_url, "https://git.example.com:3000/me/tool");
+        assert_eq!(repo.owner_url(), "https://git.example.com:3000/me");
+
+        let repo = Repository::parse("https://github.com:443/foo/bar").unwrap();
+        assert_eq!(repo.port, None);
+        let repo = Repository::parse("ssh://git@git.example.com:2222/me/tool.git").unwrap();
+        assert_eq!(repo.port, None);
+        assert_eq!(repo.web_url, "https://git.example.com/me/tool");
+    }
+
+    #[test]
+    fn test_set_host() {
+        assert_eq!(HostKind::from_name("Gitea"), Some(HostKind::Gitea));
+        assert_eq!(HostKind::from_name("forgejo"), Some(HostKind::Gitea));
+        assert_eq!(HostKind::from_name("svn"), None);
+
+        let mut repo =
+            Repository::parse("https://git.example.com:3000/me/tool/src/branch/main").unwrap();
+        assert_eq!(repo.host, HostKind::Other);
+        repo.set_host(HostKind::Gitea);
+        assert_eq!(repo.host, HostKind::Gitea);
+        assert_eq!(repo.slug(), "me/tool");
+        assert_eq!(repo.web_url, "https://git.example.com:3000/me/tool");
+
+        let mut repo = Repository::parse("https://code.example.com/group/sub/project").unwrap();
+        repo.set_host(HostKind::GitLab);
+        assert_eq!(repo.owner, "group/sub");
+        assert_eq!(repo.name, "project");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Repository::parse("not-a-url").is_err());
+        assert!(Repository::parse("https://github.com/onlyone").is_err());
+        assert!(Repository::parse("git@github.com:onlyone").is_err());
+    }
+}
@@ -1,49 +1,152 @@
 //! Unified parsing of `<!-- automdrs:NAME key="value" ... -->` tag options.
-//! Supports both key=value (quoted or unquoted) and standalone words (treated as key=true).
+//! Supports key=value (bare, or single/double quoted with spaces and escapes) and standalone
+//! words (treated as key=true).
 
+use crate::error::{Error, Result};
 use std::collections::HashMap;
+use std::ops::Range;
 
-use log::trace;
+use log::{trace, warn};
 const OPEN_PREFIX: &str = "<!-- automdrs:";
 const OPEN_SUFFIX: &str = "-->";
 
 /// Parses the option string after the block name from an open tag line.
 /// Returns a map of option names to values. Standalone words become key -> "true".
+/// Invalid options (see [`try_parse_tag_options`]) are logged and yield no options.
 ///
 /// # Examples
 /// - `<!-- automdrs:badges version downloads -->` with name "badges" → {"version": "true", "downloads": "true"}
 /// - `<!-- automdrs:contributors author="YONGQI" license="MIT" -->` → {"author": "YONGQI", "license": "MIT"}
 /// - Mixed: `<!-- automdrs:badges showCrateVersion="true" docs -->` → {"showCrateVersion": "true", "docs": "true"}
+/// - Quoted: `<!-- automdrs:contributors author="Yong Qi" -->` → {"author": "Yong Qi"}
 pub fn parse_tag_options(open_tag_line: &str, block_name: &str) -> HashMap<String, String> {
+    try_parse_tag_options(open_tag_line, block_name).unwrap_or_else(|e| {
+        warn!("{}: {}", open_tag_line.trim(), e);
+        HashMap::new()
+    })
+}
+
+/// Like [`parse_tag_options`], but reports malformed options as [`Error::TagOptions`] with the
+/// 1-based column in `open_tag_line`: an unterminated quote, a quote inside an option name,
+/// or a quoted value not followed by whitespace.
+///
+/// Quoted values may contain spaces and `=`; `\"`, `\'` and `\\` inside them are unescaped,
+/// other backslashes are kept. Bare values run to the next whitespace (`q=a=b` → `a=b`).
+pub fn try_parse_tag_options(
+    open_tag_line: &str,
+    block_name: &str,
+) -> Result<HashMap<String, String>> {
     trace!("parsing tag options: {:?}", open_tag_line);
     trace!("block name: {:?}", block_name);
-    let mut out = HashMap::new();
-    let t = open_tag_line.trim();
-    let inner = t
-        .strip_prefix(OPEN_PREFIX)
-        .and_then(|s| s.strip_suffix(OPEN_SUFFIX))
-        .map(str::trim);
-    let Some(inner) = inner else {
-        return out;
+    let Some(span) = options_span(open_tag_line, block_name) else {
+        return Ok(HashMap::new());
     };
-    let rest = match inner.strip_prefix(block_name) {
-        Some(r) => r.trim(),
-        None => return out,
+    let out: HashMap<String, String> = tokenize(open_tag_line, span)?.into_iter().collect();
+    trace!("out: {:?}", out);
+    Ok(out)
+}
+
+/// Byte range of the options in an open tag line for `block_name`, between the name and `-->`.
+fn options_span(line: &str, block_name: &str) -> Option<Range<usize>> {
+    let indent = line.len() - line.trim_start().len();
+    let tag = line.trim();
+    let inner = tag.strip_prefix(OPEN_PREFIX)?.strip_suffix(OPEN_SUFFIX)?;
+    let rest = inner.trim_start().strip_prefix(block_name)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let start =
+        indent + OPEN_PREFIX.len() + (inner.len() - inner.trim_start().len()) + block_name.len();
+    let end = indent + tag.len() - OPEN_SUFFIX.len();
+    Some(start..end)
+}
+
+/// Splits `line[span]` into `(key, value)` pairs in order; errors point into `line`.
+fn tokenize(line: &str, span: Range<usize>) -> Result<Vec<(String, String)>> {
+    let error = |at: usize, message: String| Error::TagOptions {
+        column: line[..at].chars().count() + 1,
+        message,
     };
-    for w in rest.split_whitespace() {
-        if let Some((key, value)) = w.split_once('=') {
-            let value = value
-                .strip_prefix('"')
-                .and_then(|s| s.strip_suffix('"'))
-                .or_else(|| value.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
-                .unwrap_or(value);
-            out.insert(key.to_string(), value.to_string());
-        } else if !w.is_empty() {
-            out.insert(w.to_string(), "true".to_string());
+    let mut chars = line[span.clone()]
+        .char_indices()
+        .map(|(i, c)| (span.start + i, c))
+        .peekable();
+    let mut out = Vec::new();
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let Some(&(key_at, _)) = chars.peek() else {
+            break;
+        };
+        let mut key = String::new();
+        while let Some((_, c)) =
+            chars.next_if(|(_, c)| !c.is_whitespace() && !matches!(c, '=' | '"' | '\''))
+        {
+            key.push(c);
         }
+        match chars.peek() {
+            Some(&(at, '"' | '\'')) => {
+                return Err(error(at, "unexpected quote in option name".to_string()));
+            }
+            Some(&(at, '=')) if key.is_empty() => {
+                return Err(error(at, "missing option name before `=`".to_string()));
+            }
+            Some((_, '=')) => {
+                chars.next();
+            }
+            _ => {
+                out.push((key, "true".to_string()));
+                continue;
+            }
+        }
+        let value = match chars.peek() {
+            Some(&(quote_at, quote @ ('"' | '\''))) => {
+                chars.next();
+                let unterminated = || {
+                    error(
+                        quote_at,
+                        format!(
+                            "unterminated quote in value of '{key}' (missing closing `{quote}`)"
+                        ),
+                    )
+                };
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(unterminated()),
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) if c == quote || c == '\\' => value.push(c),
+                            Some((_, c)) => {
+                                value.push('\\');
+                                value.push(c);
+                            }
+                            None => return Err(unterminated()),
+                        },
+                        Some((_, c)) if c == quote => break,
+                        Some((_, c)) => value.push(c),
+                    }
+                }
+                if let Some(&(at, c)) = chars.peek()
+                    && !c.is_whitespace()
+                {
+                    return Err(error(
+                        at,
+                        format!("expected whitespace after the quoted value of '{key}'"),
+                    ));
+                }
+                value
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+                    value.push(c);
+                }
+                value
+            }
+        };
+        trace!("option {:?} (column {}): {:?}", key, key_at, value);
+        out.push((key, value));
     }
-    trace!("out: {:?}", out);
-    out
+    Ok(out)
 }
 
 /// Returns true for values that mean "on" (e.g. "true", "yes", "1").
@@ -129,4 +232,56 @@ mod tests {
         let opts = parse_tag_options("<!-- automdrs:other version -->", "badges");
         assert!(opts.is_empty());
     }
+
+    #[test]
+    fn test_parse_tag_options_quoted() {
+        let opts = try_parse_tag_options(
+            r#"<!-- automdrs:contributors author="Yong Qi" message='Built with love' q=a=b empty="" -->"#,
+            "contributors",
+        )
+        .unwrap();
+        assert_eq!(opts["author"], "Yong Qi");
+        assert_eq!(opts["message"], "Built with love");
+        assert_eq!(opts["q"], "a=b");
+        assert_eq!(opts["empty"], "");
+
+        let opts = try_parse_tag_options(
+            r#"<!-- automdrs:x a="say \"hi\" --> ok" b='it\'s' c="C:\dir\\" d="x=y z" -->"#,
+            "x",
+        )
+        .unwrap();
+        assert_eq!(opts["a"], r#"say "hi" --> ok"#);
+        assert_eq!(opts["b"], "it's");
+        assert_eq!(opts["c"], r"C:\dir\");
+        assert_eq!(opts["d"], "x=y z");
+    }
+
+    #[test]
+    fn test_parse_tag_options_errors() {
+        let line = r#"  <!-- automdrs:contributors author="Yong Qi -->"#;
+        let err = try_parse_tag_options(line, "contributors").unwrap_err();
+        let Error::TagOptions { column, message } = &err else {
+            panic!("expected Error::TagOptions, got {err:?}");
+        };
+        assert_eq!(*column, 37);
+        assert_eq!(&line[36..37], "\"");
+        assert!(message.contains("unterminated quote"), "{message}");
+        assert!(parse_tag_options(line, "contributors").is_empty());
+
+        for (line, column) in [
+            (r#"<!-- automdrs:x a="b"c -->"#, 22),
+            (r#"<!-- automdrs:x a"b" -->"#, 18),
+            (r#"<!-- automdrs:x =b -->"#, 17),
+        ] {
+            match try_parse_tag_options(line, "x") {
+                Err(Error::TagOptions { column: c, .. }) => assert_eq!(c, column, "{line}"),
+                other => panic!("{line}: {other:?}"),
+            }
+        }
+        assert!(
+            try_parse_tag_options("<!-- automdrs:badgesx version -->", "badges")
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
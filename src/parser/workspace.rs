@@ -0,0 +1,500 @@
+//! Cargo workspace parsing: root `[workspace]` members/exclude globs → member crates and READMEs.
+
+use crate::error::{Error, Result};
+use crate::parser::cargo::Readme;
+use c12_parser::{FormatOptions, Formatted, parse_toml};
+use log::trace;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// A workspace member crate resolved from the root manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceMember {
+    pub name: String,
+    /// Directory containing the member's Cargo.toml.
+    pub manifest_dir: PathBuf,
+    /// README from `package.readme` (default `README.md` if it exists); `None` when
+    /// `readme = false` or there is no README.
+    pub readme_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RootToml {
+    workspace: Option<Workspace>,
+    package: Option<serde::de::IgnoredAny>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default, rename = "default-members")]
+    default_members: Vec<String>,
+    #[serde(default)]
+    package: WorkspacePackage,
+    #[serde(default)]
+    metadata: toml::Table,
+}
+
+/// `[workspace.package]` values that members may inherit with `field.workspace = true`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct WorkspacePackage {
+    pub version: Option<String>,
+    pub edition: Option<String>,
+    pub rust_version: Option<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    pub license: Option<String>,
+    pub license_file: Option<String>,
+    pub readme: Option<Readme>,
+    pub authors: Option<Vec<String>>,
+    pub keywords: Option<Vec<String>>,
+    pub categories: Option<Vec<String>>,
+}
+
+impl WorkspacePackage {
+    /// Paths in `[workspace.package]` are relative to the workspace root; make them
+    /// root-based so they can be joined onto any member directory.
+    fn rebased(mut self, root_dir: &Path) -> Self {
+        let root_dir = root_dir
+            .canonicalize()
+            .unwrap_or_else(|_| root_dir.to_path_buf());
+        let rebase = |path: &str| root_dir.join(path).to_string_lossy().into_owned();
+        if let Some(Readme::Path(path)) = &self.readme {
+            self.readme = Some(Readme::Path(rebase(path)));
+        }
+        if let Some(path) = &self.license_file {
+            self.license_file = Some(rebase(path));
+        }
+        self
+    }
+}
+
+/// A `[package]` field that is either set directly or inherited from `[workspace.package]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Inheritable<T> {
+    Value(T),
+    Inherited { workspace: bool },
+}
+
+impl<T: Clone> Inheritable<T> {
+    /// Returns the direct value, or looks up `key` in `[workspace.package]` when inherited.
+    pub(crate) fn resolve(
+        self,
+        key: &str,
+        inherited: &mut InheritedPackage,
+        field: fn(&WorkspacePackage) -> Option<&T>,
+    ) -> Result<T> {
+        match self {
+            Inheritable::Value(value) => Ok(value),
+            Inheritable::Inherited { workspace: true } => {
+                field(inherited.get()?).cloned().ok_or_else(|| {
+                    Error::CargoParse(format!(
+                        "package.{key}.workspace = true but [workspace.package] has no {key}"
+                    ))
+                })
+            }
+            Inheritable::Inherited { workspace: false } => Err(Error::CargoParse(format!(
+                "package.{key}.workspace must be true"
+            ))),
+        }
+    }
+}
+
+/// `[workspace.package]` of the workspace containing a member, loaded on first use.
+pub(crate) struct InheritedPackage {
+    member_dir: PathBuf,
+    package: Option<WorkspacePackage>,
+}
+
+impl InheritedPackage {
+    pub(crate) fn new(member_dir: &Path) -> Self {
+        Self {
+            member_dir: member_dir.to_path_buf(),
+            package: None,
+        }
+    }
+
+    /// Already loaded (and rebased) `[workspace.package]`, e.g. while walking the members.
+    fn loaded(member_dir: &Path, package: WorkspacePackage) -> Self {
+        Self {
+            member_dir: member_dir.to_path_buf(),
+            package: Some(package),
+        }
+    }
+
+    fn get(&mut self) -> Result<&WorkspacePackage> {
+        if self.package.is_none() {
+            let package = match find_workspace(&self.member_dir)? {
+                Some((root_dir, workspace)) => workspace.package.rebased(&root_dir),
+                None => {
+                    return Err(Error::CargoParse(format!(
+                        "no [workspace] found at or above {} for inherited fields",
+                        self.member_dir.display()
+                    )));
+                }
+            };
+            trace!("workspace.package: {:?}", package);
+            self.package = Some(package);
+        }
+        Ok(self.package.get_or_insert_default())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberToml {
+    package: MemberPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberPackage {
+    name: String,
+    readme: Option<Inheritable<Readme>>,
+}
+
+fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = std::fs::read_to_string(path)?;
+    let toml: Formatted<T> = parse_toml(&content, Some(FormatOptions::default()))
+        .map_err(|e| Error::CargoParse(format!("{}: {}", path.display(), e)))?;
+    Ok(toml.value)
+}
+
+/// Nearest Cargo.toml at or above `start_dir` with a `[workspace]` table, with its directory.
+fn find_workspace(start_dir: &Path) -> Result<Option<(PathBuf, Workspace)>> {
+    trace!("finding workspace root from {:?}", start_dir);
+    for path in find_cargo_toml::find(start_dir, None::<PathBuf>, None) {
+        let root: RootToml = read_toml(&path)?;
+        if let Some(workspace) = root.workspace {
+            let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            trace!("workspace root: {:?}", dir);
+            return Ok(Some((dir, workspace)));
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the workspace root: the nearest Cargo.toml at or above `start_dir` with a `[workspace]` table.
+/// Returns the directory containing it.
+pub fn find_root(start_dir: &Path) -> Result<PathBuf> {
+    find_workspace(start_dir)?
+        .map(|(dir, _)| dir)
+        .ok_or_else(|| {
+            Error::CargoParse(format!(
+                "no [workspace] found at or above {}",
+                start_dir.display()
+            ))
+        })
+}
+
+/// Directory of the nearest Cargo.toml at or above `start_dir`, the manifest cargo would use.
+pub fn find_manifest_dir(start_dir: &Path) -> Result<PathBuf> {
+    find_cargo_toml::find(start_dir, None::<PathBuf>, None)
+        .next()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .ok_or(Error::CargoTomlNotFound)
+}
+
+/// `[workspace.metadata]` of the workspace containing `manifest_dir`; empty outside a workspace.
+pub(crate) fn metadata(manifest_dir: &Path) -> Result<toml::Table> {
+    Ok(find_workspace(manifest_dir)?
+        .map(|(_, workspace)| workspace.metadata)
+        .unwrap_or_default())
+}
+
+/// Resolves every member of the workspace rooted at `root_dir` (sorted by path).
+/// Expands `members` globs, drops `exclude` matches, and includes the root package if any.
+pub fn members(root_dir: &Path) -> Result<Vec<WorkspaceMember>> {
+    let root: RootToml = read_toml(&root_dir.join("Cargo.toml"))?;
+    let workspace = root
+        .workspace
+        .ok_or_else(|| Error::CargoParse(format!("{}: missing [workspace]", root_dir.display())))?;
+    trace!("workspace: {:?}", workspace);
+
+    let mut dirs = member_dirs(root_dir, &workspace, &workspace.members)?;
+    if root.package.is_some() {
+        dirs.push(root_dir.to_path_buf());
+    }
+    resolve_members(root_dir, workspace, dirs)
+}
+
+/// Crate directories matched by `patterns` (member globs or paths), minus `exclude` matches.
+fn member_dirs(
+    root_dir: &Path,
+    workspace: &Workspace,
+    patterns: &[String],
+) -> Result<Vec<PathBuf>> {
+    let excluded: Vec<glob::Pattern> = workspace
+        .exclude
+        .iter()
+        .filter_map(|ex| glob::Pattern::new(&root_dir.join(ex).to_string_lossy()).ok())
+        .collect();
+
+    let mut dirs = Vec::new();
+    for pattern in patterns {
+        let full = root_dir.join(pattern);
+        let paths = glob::glob(&full.to_string_lossy())
+            .map_err(|e| Error::CargoParse(format!("workspace member '{}': {}", pattern, e)))?;
+        for dir in paths.filter_map(std::result::Result::ok) {
+            let is_excluded = excluded
+                .iter()
+                .any(|ex| ex.matches_path(&dir) || dir.starts_with(ex.as_str()));
+            if dir.join("Cargo.toml").is_file() && !is_excluded {
+                dirs.push(dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Sorts and dedups `dirs`, then reads each member with `[workspace.package]` for inherited fields.
+fn resolve_members(
+    root_dir: &Path,
+    workspace: Workspace,
+    mut dirs: Vec<PathBuf>,
+) -> Result<Vec<WorkspaceMember>> {
+    dirs.sort();
+    dirs.dedup();
+    let inherited = workspace.package.rebased(root_dir);
+    dirs.into_iter()
+        .map(|dir| member(&dir, &mut InheritedPackage::loaded(&dir, inherited.clone())))
+        .collect()
+}
+
+/// Selects packages the way cargo does for a command run on the nearest Cargo.toml at or
+/// above `manifest_dir`: `all` (`--workspace`) selects every workspace member, `packages`
+/// (`-p`) selects members by name, and otherwise the manifest's own package is used, or the
+/// workspace's `default-members` (every member if unset) for a virtual manifest.
+pub fn select(manifest_dir: &Path, packages: &[String], all: bool) -> Result<Vec<WorkspaceMember>> {
+    let manifest_path = find_cargo_toml::find(manifest_dir, None::<PathBuf>, None)
+        .next()
+        .ok_or(Error::CargoTomlNotFound)?;
+    let manifest_dir = manifest_path.parent().unwrap_or(manifest_dir);
+    let manifest: RootToml = read_toml(&manifest_path)?;
+    let Some((root_dir, workspace)) = find_workspace(manifest_dir)? else {
+        let package = member(manifest_dir, &mut InheritedPackage::new(manifest_dir))?;
+        if let Some(spec) = packages.iter().find(|spec| **spec != package.name) {
+            return Err(Error::PackageNotFound(spec.clone()));
+        }
+        return Ok(vec![package]);
+    };
+    trace!(
+        "selecting packages {:?} (all: {}) in {:?}",
+        packages, all, root_dir
+    );
+
+    if !all && packages.is_empty() {
+        if manifest.package.is_some() {
+            let inherited = workspace.package.rebased(&root_dir);
+            let mut inherited = InheritedPackage::loaded(manifest_dir, inherited);
+            return Ok(vec![member(manifest_dir, &mut inherited)?]);
+        }
+        if !workspace.default_members.is_empty() {
+            let dirs = member_dirs(&root_dir, &workspace, &workspace.default_members)?;
+            return resolve_members(&root_dir, workspace, dirs);
+        }
+    }
+    let members = members(&root_dir)?;
+    if all || packages.is_empty() {
+        return Ok(members);
+    }
+    if let Some(spec) = packages
+        .iter()
+        .find(|spec| !members.iter().any(|m| m.name == **spec))
+    {
+        return Err(Error::PackageNotFound(spec.clone()));
+    }
+    Ok(members
+        .into_iter()
+        .filter(|m| packages.contains(&m.name))
+        .collect())
+}
+
+/// Whether the Cargo.toml in `manifest_dir` is a virtual manifest (`[workspace]` without `[package]`).
+pub fn is_virtual(manifest_dir: &Path) -> bool {
+    read_toml::<RootToml>(&manifest_dir.join("Cargo.toml"))
+        .is_ok_and(|root| root.workspace.is_some() && root.package.is_none())
+}
+
+/// Reads a member; an inherited `readme` that `[workspace.package]` does not set is an
+/// error, as with cargo.
+fn member(manifest_dir: &Path, inherited: &mut InheritedPackage) -> Result<WorkspaceMember> {
+    let toml: MemberToml = read_toml(&manifest_dir.join("Cargo.toml"))?;
+    let readme = toml
+        .package
+        .readme
+        .map(|readme| readme.resolve("readme", inherited, |w| w.readme.as_ref()))
+        .transpose()?;
+    Ok(WorkspaceMember {
+        name: toml.package.name,
+        manifest_dir: manifest_dir.to_path_buf(),
+        readme_path: Readme::resolve(readme.as_ref(), manifest_dir),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_members() {
+        let root = std::env::temp_dir().join("automd_rs_test_workspace");
+        let _ = std::fs::remove_dir_all(&root);
+        write(
+            &root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skip\"]\n\n[workspace.package]\nreadme = \"SHARED.md\"\n\n[workspace.metadata.automd]\nauthor = \"ws\"\n",
+        );
+        write(
+            &root.join("crates/a/Cargo.toml"),
+            "[package]\nname = \"a\"\n",
+        );
+        write(
+            &root.join("crates/b/Cargo.toml"),
+            "[package]\nname = \"b\"\nreadme = \"docs/README.md\"\n",
+        );
+        write(
+            &root.join("crates/c/Cargo.toml"),
+            "[package]\nname = \"c\"\nreadme = false\n",
+        );
+        write(
+            &root.join("crates/skip/Cargo.toml"),
+            "[package]\nname = \"skip\"\n",
+        );
+        write(
+            &root.join("crates/d/Cargo.toml"),
+            "[package]\nname = \"d\"\nreadme.workspace = true\n",
+        );
+        write(&root.join("crates/a/README.md"), "");
+        write(
+            &root.join("crates/e/Cargo.toml"),
+            "[package]\nname = \"e\"\n",
+        );
+        std::fs::create_dir_all(root.join("crates/not-a-crate")).unwrap();
+
+        assert_eq!(
+            find_root(&root.join("crates/a"))
+                .unwrap()
+                .canonicalize()
+                .unwrap(),
+            root.canonicalize().unwrap()
+        );
+        let metadata = metadata(&root.join("crates/a")).unwrap();
+        assert_eq!(metadata["automd"]["author"].as_str(), Some("ws"));
+        let members = members(&root).unwrap();
+        let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+        assert_eq!(
+            members[0].readme_path,
+            Some(root.join("crates/a").join("README.md"))
+        );
+        assert_eq!(
+            members[1].readme_path,
+            Some(root.join("crates/b").join("docs/README.md"))
+        );
+        assert_eq!(members[2].readme_path, None);
+        assert_eq!(
+            members[3].readme_path,
+            Some(root.canonicalize().unwrap().join("SHARED.md"))
+        );
+        assert_eq!(members[4].readme_path, None);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_members_inherited_readme_error() {
+        let root = std::env::temp_dir().join("automd_rs_test_workspace_inherit");
+        let _ = std::fs::remove_dir_all(&root);
+        write(
+            &root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        );
+        write(
+            &root.join("crates/a/Cargo.toml"),
+            "[package]\nname = \"a\"\nreadme.workspace = true\n",
+        );
+        let err = members(&root).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            crate::parser::cargo::parse(&root.join("crates/a"))
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(
+            err.to_string()
+                .contains("[workspace.package] has no readme")
+        );
+        assert!(select(&root.join("crates/a"), &[], false).is_err());
+
+        write(
+            &root.join("crates/a/Cargo.toml"),
+            "[package]\nname = \"a\"\nreadme.workspace = false\n",
+        );
+        let err = select(&root, &[], true).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("package.readme.workspace must be true")
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_select() {
+        let root = std::env::temp_dir().join("automd_rs_test_select");
+        let _ = std::fs::remove_dir_all(&root);
+        write(
+            &root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\ndefault-members = [\"crates/a\"]\n",
+        );
+        for name in ["a", "b", "c"] {
+            write(
+                &root.join("crates").join(name).join("Cargo.toml"),
+                &format!("[package]\nname = \"{name}\"\n"),
+            );
+        }
+        std::fs::create_dir_all(root.join("crates/b/src")).unwrap();
+        let names = |members: Vec<WorkspaceMember>| -> Vec<String> {
+            members.into_iter().map(|m| m.name).collect()
+        };
+
+        assert!(is_virtual(&root));
+        assert!(!is_virtual(&root.join("crates/a")));
+        assert_eq!(names(select(&root, &[], false).unwrap()), ["a"]);
+        assert_eq!(names(select(&root, &[], true).unwrap()), ["a", "b", "c"]);
+        let specs = ["c".to_string(), "b".to_string()];
+        assert_eq!(names(select(&root, &specs, false).unwrap()), ["b", "c"]);
+        assert_eq!(
+            names(select(&root.join("crates/b/src"), &[], false).unwrap()),
+            ["b"]
+        );
+        assert_eq!(
+            names(select(&root.join("crates/b"), &[], true).unwrap()),
+            ["a", "b", "c"]
+        );
+        let err = select(&root, &["nope".to_string()], false).unwrap_err();
+        assert!(matches!(err, Error::PackageNotFound(spec) if spec == "nope"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_members_not_workspace() {
+        let dir = std::env::temp_dir().join("automd_rs_test_not_workspace");
+        write(&dir.join("Cargo.toml"), "[package]\nname = \"x\"\n");
+        assert!(members(&dir).is_err());
+        assert_eq!(
+            select(&dir, &["x".to_string()], false).unwrap()[0].name,
+            "x"
+        );
+        assert!(select(&dir, &["y".to_string()], false).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,57 @@
+//! Run results returned by the library API.
+
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Outcome of updating one README.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunReport {
+    pub path: PathBuf,
+    /// README content after the update; equal to the original when nothing changed.
+    #[serde(skip)]
+    pub updated: String,
+    /// Whether the file on disk was rewritten. Unchanged files are never written.
+    pub modified: bool,
+    /// Every block in document order.
+    pub blocks: Vec<BlockReport>,
+}
+
+impl RunReport {
+    /// Blocks whose body changed.
+    pub fn changed_blocks(&self) -> impl Iterator<Item = &BlockReport> {
+        self.blocks.iter().filter(|b| b.changed)
+    }
+
+    /// Blocks that failed with `keep_going`; their content was left as is.
+    pub fn failed_blocks(&self) -> impl Iterator<Item = &BlockReport> {
+        self.blocks.iter().filter(|b| b.error.is_some())
+    }
+}
+
+/// What happened to one block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockReport {
+    pub name: String,
+    /// Effective options: config defaults merged with the open tag's options.
+    pub options: BTreeMap<String, String>,
+    /// 1-based line of the open tag.
+    pub start_line: usize,
+    /// 1-based line of the close tag, if the block is closed.
+    pub end_line: Option<usize>,
+    pub old_body: Vec<String>,
+    /// Body after the update; equal to `old_body` for disabled and failed blocks.
+    pub new_body: Vec<String>,
+    pub changed: bool,
+    /// Time spent generating the block; serialized as `duration_ms`.
+    #[serde(rename = "duration_ms", serialize_with = "as_millis")]
+    pub duration: Duration,
+    pub warnings: Vec<String>,
+    /// Error message if generation failed (with `keep_going`).
+    pub error: Option<String>,
+}
+
+fn as_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
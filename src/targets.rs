@@ -0,0 +1,90 @@
+//! Target Markdown files: literal paths and glob patterns such as `docs/**/*.md`.
+
+use crate::error::{Error, Result};
+use log::trace;
+use std::path::{Path, PathBuf};
+
+/// Whether `path` contains glob metacharacters (`*`, `?`, `[`).
+pub fn is_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Expands glob patterns into the files they match (sorted per pattern) and keeps literal
+/// paths as given, so a missing file is reported for that file. A path that exists is taken
+/// literally even if it looks like a pattern (e.g. `docs/[draft].md`). Duplicates are dropped,
+/// keeping the first occurrence. A pattern that is invalid or matches no file is an error.
+pub fn expand(patterns: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for pattern in patterns {
+        let matches = if is_pattern(pattern) && !pattern.exists() {
+            let text = pattern.to_string_lossy();
+            let mut matches: Vec<PathBuf> = glob::glob(&text)
+                .map_err(|e| Error::Pattern(format!("{}: {}", text, e)))?
+                .filter_map(std::result::Result::ok)
+                .filter(|path| path.is_file())
+                .collect();
+            if matches.is_empty() {
+                return Err(Error::Pattern(format!("{}: no matching files", text)));
+            }
+            matches.sort();
+            matches
+        } else {
+            vec![pattern.clone()]
+        };
+        for path in matches {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    trace!("targets: {:?} -> {:?}", patterns, files);
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let dir = std::env::temp_dir().join("automd_rs_test_targets");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs/guide")).unwrap();
+        for file in [
+            "README.md",
+            "docs/b.md",
+            "docs/a.md",
+            "docs/guide/c.md",
+            "docs/x.txt",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let files = expand(&[
+            dir.join("README.md"),
+            dir.join("docs/**/*.md"),
+            dir.join("docs/a.md"),
+            dir.join("MISSING.md"),
+        ])
+        .unwrap();
+        assert_eq!(
+            files,
+            [
+                dir.join("README.md"),
+                dir.join("docs/a.md"),
+                dir.join("docs/b.md"),
+                dir.join("docs/guide/c.md"),
+                dir.join("MISSING.md"),
+            ]
+        );
+        let err = expand(&[dir.join("none/*.md")]).unwrap_err();
+        assert!(err.to_string().contains("no matching files"));
+        assert!(expand(&[dir.join("[")]).is_err());
+
+        std::fs::write(dir.join("docs/[draft].md"), "").unwrap();
+        let files = expand(&[dir.join("docs/[draft].md")]).unwrap();
+        assert_eq!(files, [dir.join("docs/[draft].md")]);
+        let err = expand(&[dir.join("docs/[draft]-old.md")]).unwrap_err();
+        assert!(err.to_string().contains("no matching files"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,243 @@
+//! Polling watch mode (std only): the files a run depends on and when they change.
+
+use crate::config::Config;
+use crate::parser::{readme, workspace};
+use crate::targets;
+use log::trace;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Files a run over `targets` reads: the manifest (and the workspace root manifest), the
+/// config file, the targets themselves and the `src` of their `file` blocks. Files that
+/// cannot be read yet are still listed, so creating them triggers a run.
+pub fn watched_files(manifest_dir: &Path, targets: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![manifest_dir.join("Cargo.toml")];
+    if let Ok(root) = workspace::find_root(manifest_dir) {
+        files.push(root.join("Cargo.toml"));
+    }
+    files.extend(Config::find_file(manifest_dir));
+    let automd = crate::parse_manifest(manifest_dir)
+        .and_then(|manifest| Config::load(manifest_dir, &manifest))
+        .unwrap_or_default();
+    for target in targets {
+        files.push(target.clone());
+        let Ok(content) = std::fs::read_to_string(target) else {
+            continue;
+        };
+        for request in readme::parse_readme_blocks(&content) {
+            if request.name != "file" {
+                continue;
+            }
+            if let Some(src) = automd.options("file", &request.open_tag_line).get("src") {
+                files.push(manifest_dir.join(src));
+            }
+        }
+    }
+    let mut unique: Vec<PathBuf> = Vec::with_capacity(files.len());
+    for file in files {
+        if !unique.contains(&file) {
+            unique.push(file);
+        }
+    }
+    trace!("watched files: {:?}", unique);
+    unique
+}
+
+/// Modification time and size of each watched file; `None` while a file is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    stamps: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+    /// Watched glob patterns and the files they matched, sorted.
+    patterns: Vec<PathBuf>,
+    matched: Vec<PathBuf>,
+}
+
+impl Snapshot {
+    pub fn take(paths: Vec<PathBuf>) -> Self {
+        let stamps = paths
+            .into_iter()
+            .map(|path| {
+                let stamp = std::fs::metadata(&path)
+                    .and_then(|m| Ok((m.modified()?, m.len())))
+                    .ok();
+                (path, stamp)
+            })
+            .collect();
+        Self {
+            stamps,
+            patterns: Vec::new(),
+            matched: Vec::new(),
+        }
+    }
+
+    /// Also watches the glob `patterns` (e.g. `docs/**/*.md`), re-expanded on every poll, so
+    /// a file that starts or stops matching one counts as changed. Existing literal paths
+    /// and non-patterns are skipped, as in [`targets::expand`].
+    pub fn with_patterns(mut self, patterns: &[PathBuf]) -> Self {
+        self.patterns = patterns
+            .iter()
+            .filter(|p| targets::is_pattern(p) && !p.exists())
+            .cloned()
+            .collect();
+        self.matched = glob_matches(&self.patterns);
+        self
+    }
+
+    fn retake(&self) -> Self {
+        Self::take(self.paths()).with_patterns(&self.patterns)
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.stamps.iter().map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Watched files whose stamp differs from `other`'s, then files matching a pattern in
+    /// only one of the two.
+    pub fn changed(&self, other: &Snapshot) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .stamps
+            .iter()
+            .zip(&other.stamps)
+            .filter(|(a, b)| a != b)
+            .map(|((path, _), _)| path.clone())
+            .collect();
+        for (a, b) in [(self, other), (other, self)] {
+            changed.extend(
+                a.matched
+                    .iter()
+                    .filter(|path| !b.matched.contains(path) && !changed.contains(path))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+        }
+        changed
+    }
+}
+
+/// Files matched by any of `patterns`; invalid patterns and unreadable entries are ignored.
+fn glob_matches(patterns: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = patterns
+        .iter()
+        .filter_map(|pattern| glob::glob(&pattern.to_string_lossy()).ok())
+        .flatten()
+        .filter_map(std::result::Result::ok)
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Polls every `interval` until a file of `snapshot` changes, then waits until nothing has
+/// changed for `debounce` (e.g. an editor saving several files) and returns the changed files.
+/// Take the snapshot after writing, so the tool's own writes are not reported.
+pub fn wait_for_change(
+    snapshot: &Snapshot,
+    interval: Duration,
+    debounce: Duration,
+) -> Vec<PathBuf> {
+    loop {
+        std::thread::sleep(interval);
+        let mut current = snapshot.retake();
+        if current == *snapshot {
+            continue;
+        }
+        loop {
+            std::thread::sleep(debounce);
+            let next = snapshot.retake();
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        return current.changed(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_files() {
+        let dir = std::env::temp_dir().join("automd_rs_test_watch");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"w\"\n").unwrap();
+        std::fs::write(
+            dir.join("README.md"),
+            "<!-- automdrs:file src=\"src/main.rs\" -->\n<!-- /automdrs -->\n<!-- automdrs:cargo-add -->\n<!-- /automdrs -->\n",
+        )
+        .unwrap();
+        let files = watched_files(&dir, &[dir.join("README.md"), dir.join("README.md")]);
+        assert_eq!(
+            files,
+            [
+                dir.join("Cargo.toml"),
+                dir.join("README.md"),
+                dir.join("src/main.rs")
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wait_for_change() {
+        let dir = std::env::temp_dir().join("automd_rs_test_watch_change");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("README.md");
+        std::fs::write(&file, "a").unwrap();
+        let snapshot = Snapshot::take(vec![file.clone(), dir.join("new.md")]);
+        assert!(
+            Snapshot::take(snapshot.paths())
+                .changed(&snapshot)
+                .is_empty()
+        );
+
+        let writer = {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(30));
+                std::fs::write(dir.join("new.md"), "b").unwrap();
+            })
+        };
+        let changed = wait_for_change(
+            &snapshot,
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+        );
+        writer.join().unwrap();
+        assert_eq!(changed, [dir.join("new.md")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wait_for_new_match() {
+        let dir = std::env::temp_dir().join("automd_rs_test_watch_pattern");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/a.md"), "a").unwrap();
+        let pattern = dir.join("docs/**/*.md");
+        let snapshot = Snapshot::take(vec![dir.join("docs/a.md")]).with_patterns(&[pattern]);
+        assert!(snapshot.retake().changed(&snapshot).is_empty());
+
+        let writer = {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(30));
+                std::fs::create_dir_all(dir.join("docs/guide")).unwrap();
+                std::fs::write(dir.join("docs/guide/new.md"), "b").unwrap();
+                std::fs::write(dir.join("docs/notes.txt"), "c").unwrap();
+            })
+        };
+        let changed = wait_for_change(
+            &snapshot,
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+        );
+        writer.join().unwrap();
+        assert_eq!(changed, [dir.join("docs/guide/new.md")]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
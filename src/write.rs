@@ -0,0 +1,95 @@
+//! Crash-safe README writes: temp file in the same directory, then rename over the original.
+
+use crate::error::Result;
+use log::trace;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// `README.md` → `README.md.bak`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Replaces `path` with `contents` atomically: readers see either the old or the new file,
+/// never a truncated one. Permissions of an existing file are kept and symlinks are written
+/// through. With `backup`, the previous content is copied to [`backup_path`] first.
+pub fn write_atomic(path: &Path, contents: &str, backup: bool) -> Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = dir.join(tmp_name);
+    trace!("writing {:?} via {:?}", path, tmp);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            std::fs::set_permissions(&tmp, metadata.permissions())?;
+            if backup {
+                let backup = backup_path(&path);
+                trace!("backup: {:?}", backup);
+                std::fs::copy(&path, backup)?;
+            }
+        }
+        std::fs::rename(&tmp, &path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_path() {
+        assert_eq!(
+            backup_path(Path::new("docs/README.md")),
+            PathBuf::from("docs/README.md.bak")
+        );
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join("automd_rs_test_write_atomic");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("README.md");
+
+        write_atomic(&path, "new file", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new file");
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, "second", false).unwrap();
+        assert!(!backup_path(&path).exists());
+        write_atomic(&path, "third", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third");
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&path)).unwrap(),
+            "second"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+            write_atomic(&path, "fourth", false).unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}